@@ -0,0 +1,179 @@
+use colored::*;
+
+/// Number of unchanged lines displayed around changes.
+const CONTEXT: usize = 3;
+
+/// A single line of a diff, referencing lines of the original (`old`) and
+/// formatted (`new`) texts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Line {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Build a unified diff between the `old` and `new` texts.
+/// Colors are applied with `colored`, which can be disabled globally with
+/// `colored::control::set_override(false)`.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let old: Vec<_> = old.split_inclusive('\n').collect();
+    let new: Vec<_> = new.split_inclusive('\n').collect();
+    let lines = diff_lines(&old, &new);
+
+    let mut output = String::new();
+    output.push_str(&format!("{}\n", format!("--- {}", old_name).bold()));
+    output.push_str(&format!("{}\n", format!("+++ {}", new_name).bold()));
+
+    for hunk in hunks(&lines) {
+        let hunk = &lines[hunk.0..hunk.1];
+
+        // Line numbers are 1-based, and 0 is used for empty ranges.
+        let (mut old_start, mut old_len, mut new_start, mut new_len) = (None, 0, None, 0);
+        for line in hunk {
+            match *line {
+                Line::Equal(i, j) => {
+                    old_start.get_or_insert(i);
+                    new_start.get_or_insert(j);
+                    old_len += 1;
+                    new_len += 1;
+                }
+                Line::Delete(i) => {
+                    old_start.get_or_insert(i);
+                    old_len += 1;
+                }
+                Line::Insert(j) => {
+                    new_start.get_or_insert(j);
+                    new_len += 1;
+                }
+            }
+        }
+
+        let header = format!(
+            "@@ -{},{} +{},{} @@",
+            old_start.map(|i| i + 1).unwrap_or(0),
+            old_len,
+            new_start.map(|j| j + 1).unwrap_or(0),
+            new_len
+        );
+        output.push_str(&format!("{}\n", header.cyan()));
+
+        for line in hunk {
+            let (sign, text) = match *line {
+                Line::Equal(i, _) => (' ', old[i]),
+                Line::Delete(i) => ('-', old[i]),
+                Line::Insert(j) => ('+', new[j]),
+            };
+
+            let content = format!("{}{}", sign, text.trim_end_matches('\n'));
+            let content = match sign {
+                '-' => content.red(),
+                '+' => content.green(),
+                _ => content.normal(),
+            };
+            output.push_str(&format!("{}\n", content));
+
+            if !text.ends_with('\n') {
+                output.push_str("\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    output
+}
+
+/// Group diff lines into hunks, as ranges of `lines`.
+/// Each hunk contains changes surrounded by at most `CONTEXT` unchanged lines,
+/// and hunks closer than twice the context are merged.
+fn hunks(lines: &[Line]) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = vec![];
+
+    for (i, line) in lines.iter().enumerate() {
+        if let Line::Equal(..) = line {
+            continue;
+        }
+
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + 1 + CONTEXT).min(lines.len());
+
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    hunks
+}
+
+/// Compute the shortest edit script between `old` and `new` using the
+/// Myers diff algorithm.
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<Line> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let index = |k: isize| (k + offset) as usize;
+
+    // `v[k]` contains the furthest `x` reached on diagonal `k`.
+    let mut v = vec![0isize; 2 * max + 3];
+    let mut trace = vec![];
+
+    'search: for d in 0..=max as isize {
+        trace.push(v.clone());
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+
+            v[index(k)] = x;
+
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    // Backtrack to build the edit script.
+    let mut lines = vec![];
+    let (mut x, mut y) = (n, m);
+
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+
+        let prev_k = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[index(prev_k)];
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            lines.push(Line::Equal(x as usize, y as usize));
+        }
+
+        if d > 0 {
+            if x == prev_x {
+                lines.push(Line::Insert(prev_y as usize));
+            } else {
+                lines.push(Line::Delete(prev_x as usize));
+            }
+        }
+
+        x = prev_x;
+        y = prev_y;
+    }
+
+    lines.reverse();
+    lines
+}
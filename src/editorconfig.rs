@@ -0,0 +1,177 @@
+use {
+    crate::Indent,
+    std::{
+        collections::BTreeMap,
+        path::{Path, PathBuf},
+    },
+};
+
+const EDITORCONFIG_FILE: &str = ".editorconfig";
+
+/// A parsed `.editorconfig` file.
+struct EditorConfig {
+    /// Folder containing the file, from which patterns are resolved.
+    dir: PathBuf,
+    /// Stop searching parent folders.
+    root: bool,
+    sections: Vec<Section>,
+}
+
+/// A `[pattern]` section and its properties.
+struct Section {
+    patterns: Vec<glob::Pattern>,
+    properties: BTreeMap<String, String>,
+}
+
+/// Indentation configured by the `.editorconfig` files applying to the file at
+/// `path`, if any.
+pub fn indent(path: &Path) -> Option<Indent> {
+    let path = std::env::current_dir().ok()?.join(path);
+
+    // Files from the closest to the furthest.
+    let mut files = vec![];
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if let Ok(text) = std::fs::read_to_string(current.join(EDITORCONFIG_FILE)) {
+            let file = parse(current, &text);
+            let root = file.root;
+            files.push(file);
+
+            if root {
+                break;
+            }
+        }
+
+        dir = current.parent();
+    }
+
+    // Closest files and last sections take precedence.
+    let mut properties = BTreeMap::new();
+    for file in files.iter().rev() {
+        let relative_path = match path.strip_prefix(&file.dir) {
+            Ok(relative_path) => relative_path,
+            Err(_) => continue,
+        };
+
+        for section in &file.sections {
+            if section.matches(relative_path) {
+                properties.extend(section.properties.clone());
+            }
+        }
+    }
+
+    let size = |key: &str| properties.get(key).and_then(|v| v.parse::<usize>().ok());
+
+    match properties.get("indent_style").map(String::as_str) {
+        Some("tab") => Some(Indent::Tab),
+        Some("space") => Some(Indent::Spaces(
+            size("indent_size")
+                .or_else(|| size("tab_width"))
+                .unwrap_or(4),
+        )),
+        _ => None,
+    }
+}
+
+impl Section {
+    fn matches(&self, relative_path: &Path) -> bool {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+
+        self.patterns
+            .iter()
+            .any(|pattern| pattern.matches_path_with(relative_path, options))
+    }
+}
+
+/// Parse an `.editorconfig` file located in `dir`.
+/// Sections with unsupported patterns are ignored.
+fn parse(dir: &Path, text: &str) -> EditorConfig {
+    let mut file = EditorConfig {
+        dir: dir.to_path_buf(),
+        root: false,
+        sections: vec![],
+    };
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(pattern) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            file.sections.push(Section {
+                patterns: compile_pattern(pattern),
+                properties: BTreeMap::new(),
+            });
+            continue;
+        }
+
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim().to_lowercase(), value.trim().to_lowercase()),
+            None => continue,
+        };
+
+        match file.sections.last_mut() {
+            Some(section) => {
+                section.properties.insert(key, value);
+            }
+            // Preamble
+            None if key == "root" => file.root = value == "true",
+            None => (),
+        }
+    }
+
+    file
+}
+
+/// Convert an editorconfig pattern into glob patterns.
+/// Patterns without `/` match files in any folder, while patterns with `/` are
+/// relative to the `.editorconfig` folder. `{a,b}` alternatives are expanded,
+/// and `**` followed by a file pattern (`src/**.toml`) matches in any subfolder.
+fn compile_pattern(pattern: &str) -> Vec<glob::Pattern> {
+    let pattern = if pattern.contains('/') {
+        pattern.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{}", pattern)
+    };
+
+    // `glob` only supports `**` as a whole path component.
+    let mut parts = pattern.split("**");
+    let mut pattern = parts.next().unwrap_or("").to_string();
+    for part in parts {
+        pattern.push_str("**");
+        if !(part.is_empty() || part.starts_with('/')) {
+            pattern.push_str("/*");
+        }
+        pattern.push_str(part);
+    }
+
+    expand_braces(&pattern)
+        .iter()
+        .filter_map(|pattern| glob::Pattern::new(pattern).ok())
+        .collect()
+}
+
+/// Expand `{a,b}` alternatives into multiple patterns.
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let start = match pattern.find('{') {
+        Some(start) => start,
+        None => return vec![pattern.to_string()],
+    };
+
+    let end = match pattern[start..].find('}') {
+        Some(end) => start + end,
+        None => return vec![pattern.to_string()],
+    };
+
+    let (head, tail) = (&pattern[..start], &pattern[end + 1..]);
+
+    pattern[start + 1..end]
+        .split(',')
+        .flat_map(|alternative| expand_braces(&format!("{}{}{}", head, alternative, tail)))
+        .collect()
+}
@@ -0,0 +1,210 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Error returned when formatting a TOML document from memory.
+#[derive(Debug, Clone)]
+pub enum FormatError {
+    /// The input is not a valid TOML document.
+    Parse(toml_edit::TomlError),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "invalid TOML document: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<toml_edit::TomlError> for FormatError {
+    fn from(x: toml_edit::TomlError) -> Self {
+        Self::Parse(x)
+    }
+}
+
+/// Error that can occur while processing files.
+#[derive(Debug)]
+pub enum Error {
+    /// A file (or stdin/stdout) could not be read or written.
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A document is not valid TOML.
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The configuration is invalid.
+    /// Line and column are 1-based, and provided when the error can be located
+    /// in the config file.
+    Config {
+        path: Option<PathBuf>,
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+    /// A document is not properly formatted (`--check`).
+    CheckFailed { path: PathBuf },
+}
+
+impl Error {
+    pub(crate) fn io(path: impl AsRef<Path>, source: std::io::Error) -> Self {
+        Self::Io {
+            path: path.as_ref().to_path_buf(),
+            source,
+        }
+    }
+
+    /// Error while deserializing the config file at `path` containing `text`.
+    pub(crate) fn config(path: impl AsRef<Path>, text: &str, error: toml::de::Error) -> Self {
+        let mut message = error.to_string();
+        let mut location = error.line_col();
+
+        if let Some((line, column)) = location {
+            // Location is stored separately.
+            let suffix = format!(" at line {} column {}", line + 1, column + 1);
+            if let Some(stripped) = message.strip_suffix(&suffix) {
+                message = stripped.to_string();
+            }
+        }
+
+        // Errors about unknown fields are reported at the start of their table,
+        // so we try to find the misspelt key in that table instead.
+        if let Some(key) = message
+            .strip_prefix("unknown field `")
+            .and_then(|s| s.split('`').next())
+        {
+            let table_line = location.map_or(0, |(line, _)| line);
+            if let Some(found) = find_key(text, key, table_line) {
+                location = Some(found);
+            }
+        }
+
+        Self::Config {
+            path: Some(path.as_ref().to_path_buf()),
+            line: location.map(|(line, _)| line + 1),
+            column: location.map(|(_, column)| column + 1),
+            message,
+        }
+    }
+
+    /// Convert a `FormatError` into an `Error`, using the input text to compute
+    /// the location of parsing errors.
+    pub(crate) fn format(path: impl AsRef<Path>, text: &str, error: FormatError) -> Self {
+        match error {
+            FormatError::Parse(e) => {
+                let offset = e.span().map(|span| span.start).unwrap_or(0);
+                let (line, column) = line_column(text, offset);
+
+                Self::Parse {
+                    path: path.as_ref().to_path_buf(),
+                    line,
+                    column,
+                    message: e
+                        .message()
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => {
+                write!(
+                    f,
+                    "Error while accessing \"{}\" : {}",
+                    path.display(),
+                    source
+                )
+            }
+            Self::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Invalid TOML in \"{}\" at line {}, column {} : {}",
+                path.display(),
+                line,
+                column,
+                message
+            ),
+            Self::Config {
+                path,
+                line,
+                column,
+                message,
+            } => {
+                write!(f, "Invalid config")?;
+                if let Some(path) = path {
+                    write!(f, " \"{}\"", path.display())?;
+                }
+                if let (Some(line), Some(column)) = (line, column) {
+                    write!(f, " at line {}, column {}", line, column)?;
+                }
+                write!(f, " : {}", message)
+            }
+            Self::CheckFailed { path } => write!(f, "Check fails : {}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Compute the 1-based line and column of a byte offset in a text.
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(text.len());
+    let before = text.get(..offset).unwrap_or(text);
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map(|s| s.chars().count())
+        .unwrap_or(0)
+        + 1;
+
+    (line, column)
+}
+
+/// Find the 0-based line and column of the first line defining `key` from
+/// `from_line`, either as `key = ...` or as a `[key]`/`[[key]]` table header.
+fn find_key(text: &str, key: &str, from_line: usize) -> Option<(usize, usize)> {
+    text.lines()
+        .enumerate()
+        .skip(from_line)
+        .find_map(|(line, content)| {
+            let trimmed = content.trim_start();
+            let column = content.len() - trimmed.len();
+
+            let header = trimmed.trim_start_matches('[');
+            if header.len() != trimmed.len() {
+                let rest = header.trim_start().strip_prefix(key)?.trim_start();
+                return (rest.starts_with(']') || rest.starts_with('.')).then_some((line, column));
+            }
+
+            let rest = trimmed.strip_prefix(key)?;
+            rest.trim_start().starts_with('=').then_some((line, column))
+        })
+}
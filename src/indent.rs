@@ -0,0 +1,55 @@
+use {
+    serde::{Deserialize, Serialize},
+    std::fmt,
+};
+
+/// Indentation emitted by the formatter, configured either as `"tab"` or as a
+/// number of spaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "RawIndent", into = "RawIndent")]
+pub enum Indent {
+    #[default]
+    Tab,
+    Spaces(usize),
+}
+
+/// Representation of `Indent` in the config.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawIndent {
+    Spaces(usize),
+    Style(String),
+}
+
+impl fmt::Display for Indent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tab => f.write_str("\t"),
+            Self::Spaces(n) => write!(f, "{:n$}", ""),
+        }
+    }
+}
+
+impl TryFrom<RawIndent> for Indent {
+    type Error = String;
+
+    fn try_from(x: RawIndent) -> Result<Self, Self::Error> {
+        match x {
+            RawIndent::Spaces(n) => Ok(Self::Spaces(n)),
+            RawIndent::Style(style) if style == "tab" => Ok(Self::Tab),
+            RawIndent::Style(style) => Err(format!(
+                "invalid indent \"{}\", expected \"tab\" or a number of spaces",
+                style
+            )),
+        }
+    }
+}
+
+impl From<Indent> for RawIndent {
+    fn from(x: Indent) -> Self {
+        match x {
+            Indent::Tab => Self::Style("tab".to_string()),
+            Indent::Spaces(n) => Self::Spaces(n),
+        }
+    }
+}
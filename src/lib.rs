@@ -2,38 +2,264 @@ use {
     colored::*,
     serde::{Deserialize, Serialize},
     std::{
+        borrow::Cow,
         cmp::Ordering,
-        collections::BTreeMap,
-        error::Error,
+        collections::{BTreeMap, BTreeSet},
         ffi::OsString,
         fs::File,
-        io::Write,
+        io::{Read, Write},
         path::{Path, PathBuf},
     },
     structopt::StructOpt,
-    toml_edit::{Array, Decor, Document, InlineTable, Item, RawString, Table, Value},
+    toml_edit::{
+        Array, ArrayOfTables, Decor, Document, InlineTable, Item, RawString, Table, Value,
+    },
+};
+
+mod diff;
+mod editorconfig;
+mod error;
+mod indent;
+mod literal;
+mod pattern;
+mod preset;
+mod quote;
+mod sort;
+
+pub use {
+    diff::unified_diff,
+    error::{Error, FormatError},
+    indent::Indent,
+    literal::normalize_literal,
+    pattern::{FilePattern, KeyPattern},
+    preset::Preset,
+    quote::QuoteStyle,
+    sort::{compare_values, SortMode},
 };
 
 /// Type alias for shorter return types.
-pub type Res<T = ()> = Result<T, Box<dyn Error>>;
+pub type Res<T = ()> = Result<T, Error>;
+
+pub fn run(mut opt: Opt, config: Config) -> Res<Report> {
+    let mut configs = ConfigResolver {
+        root: opt.config_root()?,
+        default: config,
+        nearest: opt.per_file_config && opt.config.is_none(),
+        loaded: BTreeMap::new(),
+        processed: BTreeMap::new(),
+    };
+    let mut report = Report::default();
+    let check = opt.check || opt.diff;
+
+    if opt.reads_stdin() {
+        let path = opt.stdin_filepath.as_deref();
+        let outcome = match path {
+            Some(path) => configs.resolve(&current_dir()?.join(path)),
+            None => configs.resolve_default(),
+        }
+        .and_then(|(root, config)| {
+            let config = match path {
+                Some(path) => config.with_editorconfig(path),
+                None => Cow::Borrowed(config),
+            };
+            config.process_stdin(path, &root, check, opt.diff)
+        });
+        report.push(path.unwrap_or_else(|| Path::new(STDIN)), outcome);
 
-pub fn run(mut opt: Opt, config: Config) -> Res {
-    let config: ProcessedConfig = config.into();
+        return Ok(report);
+    }
 
     if opt.files.is_empty() && opt.folder.is_empty() {
-        opt.folder.push(std::env::current_dir()?);
+        opt.folder.push(current_dir()?);
     }
 
     for folder in opt.folder {
-        let files = find_files_recursively(folder, "toml", !opt.silent, &config.excludes);
-        opt.files.extend(files);
+        let files = find_files_recursively(folder, "toml", !opt.silent, &configs.default.excludes)?;
+
+        // Files found by scanning folders are also checked against the `excludes`
+        // of their nearest config.
+        for file in files {
+            if configs.nearest {
+                match configs.resolve(&file) {
+                    Ok((root, config)) if config.excludes(&file, &root)? => continue,
+                    Ok(_) => (),
+                    Err(e) => {
+                        report.push(&file, Err(e));
+                        continue;
+                    }
+                }
+            }
+
+            opt.files.push(file);
+        }
     }
 
     for file in opt.files {
-        config.process_file(file, opt.check, !opt.silent)?;
+        let outcome = configs.resolve(&file).and_then(|(_, config)| {
+            config
+                .with_editorconfig(&file)
+                .process_file(&file, check, opt.diff, !opt.silent)
+        });
+        report.push(file, outcome);
     }
 
-    Ok(())
+    Ok(report)
+}
+
+/// Resolve which config should be used for each file.
+struct ConfigResolver {
+    /// Directory of the default config, used to resolve relative paths.
+    root: PathBuf,
+    /// Config provided to `run`.
+    default: Config,
+    /// Use the nearest `toml-maid.toml` of each file instead of `default`.
+    nearest: bool,
+    /// Already loaded config files, by path.
+    loaded: BTreeMap<PathBuf, Config>,
+    /// Already processed configs, by config file path (`None` for `default`)
+    /// and indices of the applied overrides.
+    processed: BTreeMap<(Option<PathBuf>, Vec<usize>), ProcessedConfig>,
+}
+
+impl ConfigResolver {
+    /// Config to use for the provided file, along with its directory.
+    fn resolve(&mut self, file: &Path) -> Res<(PathBuf, &ProcessedConfig)> {
+        let file = current_dir()?.join(file);
+        let dir = file.parent().unwrap_or(&file);
+
+        let path = if self.nearest {
+            Config::locate(dir)
+        } else {
+            None
+        };
+
+        if let Some(path) = &path {
+            if !self.loaded.contains_key(path) {
+                let config = Config::load(path)?;
+                self.loaded.insert(path.clone(), config);
+            }
+        }
+
+        let (root, config) = match &path {
+            Some(path) => (
+                path.parent().unwrap_or(path).to_path_buf(),
+                &self.loaded[path],
+            ),
+            None => (self.root.clone(), &self.default),
+        };
+
+        let relative_path = file.strip_prefix(&root).unwrap_or(&file);
+        let overrides = config.matching_overrides(relative_path);
+        let key = (path, overrides);
+
+        if !self.processed.contains_key(&key) {
+            let processed = config.with_overrides(&key.1).into();
+            self.processed.insert(key.clone(), processed);
+        }
+
+        Ok((root, &self.processed[&key]))
+    }
+
+    /// Default config, without any override.
+    fn resolve_default(&mut self) -> Res<(PathBuf, &ProcessedConfig)> {
+        let processed = self
+            .processed
+            .entry((None, vec![]))
+            .or_insert_with(|| self.default.with_overrides(&[]).into());
+
+        Ok((self.root.clone(), processed))
+    }
+}
+
+/// Outcome of processing a single file.
+#[derive(Debug)]
+pub enum Outcome {
+    /// The file was already properly formatted.
+    Unchanged,
+    /// The file has been overwritten with the formatted content.
+    Reformatted,
+    /// The file is not properly formatted (`--check`).
+    WouldReformat,
+    /// The file could not be processed.
+    Failed(Error),
+}
+
+/// Outcome of processing a file, with its path.
+#[derive(Debug)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub outcome: Outcome,
+}
+
+/// Outcomes of all the files processed by `run`.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub files: Vec<FileReport>,
+}
+
+impl Report {
+    fn push(&mut self, path: impl AsRef<Path>, outcome: Res<Outcome>) {
+        let path = path.as_ref().to_path_buf();
+        let outcome = outcome.unwrap_or_else(|e| {
+            eprintln!("{}", e.to_string().red());
+            Outcome::Failed(e)
+        });
+
+        self.files.push(FileReport { path, outcome });
+    }
+
+    /// Files that are not properly formatted (`--check`).
+    pub fn check_failures(&self) -> impl Iterator<Item = &Path> {
+        self.files
+            .iter()
+            .filter(|file| matches!(file.outcome, Outcome::WouldReformat))
+            .map(|file| file.path.as_path())
+    }
+
+    /// Errors that occured while processing files.
+    pub fn errors(&self) -> impl Iterator<Item = &Error> {
+        self.files.iter().filter_map(|file| match &file.outcome {
+            Outcome::Failed(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    /// Process exit code matching this report:
+    /// - `3` if any file could not be processed,
+    /// - `2` if any file is not properly formatted (`--check`),
+    /// - `0` otherwise.
+    pub fn exit_code(&self) -> i32 {
+        if self.errors().next().is_some() {
+            3
+        } else if self.check_failures().next().is_some() {
+            2
+        } else {
+            0
+        }
+    }
+
+    /// Turn the report into an error if any file failed or is not properly
+    /// formatted. The first encountered error is returned.
+    pub fn into_result(mut self) -> Res<Self> {
+        let failed = self
+            .files
+            .iter()
+            .position(|file| matches!(file.outcome, Outcome::Failed(_)));
+
+        if let Some(i) = failed {
+            match self.files.swap_remove(i).outcome {
+                Outcome::Failed(e) => return Err(e),
+                _ => unreachable!("outcome at position {} is a failure", i),
+            }
+        }
+
+        if let Some(path) = self.check_failures().next() {
+            let path = path.to_path_buf();
+            return Err(Error::CheckFailed { path });
+        }
+
+        Ok(self)
+    }
 }
 
 /// A TOML entry. Generic to support both `Item` and `Value` entries.
@@ -43,11 +269,12 @@ struct Entry<T> {
     decor: Decor,
 }
 
-#[derive(StructOpt, Debug, Clone)]
+#[derive(StructOpt, Debug, Clone, Default)]
 pub struct Opt {
     /// List of .toml files to format.
     /// If no files are provided, and `--scan-folder` is not used then
     /// it will scan for all .toml files in the current directory.
+    /// Use `-` to read from stdin (same as `--stdin`).
     #[structopt(name = "FILE", parse(from_os_str))]
     pub files: Vec<PathBuf>,
 
@@ -60,53 +287,378 @@ pub struct Opt {
     #[structopt(short, long)]
     pub check: bool,
 
+    /// Print a unified diff of the changes for each file that is not properly
+    /// formatted. Implies `--check`.
+    #[structopt(short, long)]
+    pub diff: bool,
+
+    /// Disables colors in the output, which is useful for log files.
+    #[structopt(long)]
+    pub no_color: bool,
+
     /// Disables verbose messages.
     #[structopt(short, long)]
     pub silent: bool,
+
+    /// Read a document from stdin and print the formatted result to stdout.
+    #[structopt(long)]
+    pub stdin: bool,
+
+    /// Path of the config file to use, instead of searching for a `toml-maid.toml`
+    /// in the current directory and its parents.
+    #[structopt(long, parse(from_os_str))]
+    pub config: Option<PathBuf>,
+
+    /// Use the nearest `toml-maid.toml` of each formatted file (searching in the
+    /// file directory and its parents), instead of a single config for all files.
+    #[structopt(long)]
+    pub per_file_config: bool,
+
+    /// Path of the document read from stdin.
+    /// Config discovery and `excludes` are resolved as if the document was
+    /// located at this path.
+    #[structopt(long, parse(from_os_str))]
+    pub stdin_filepath: Option<PathBuf>,
+}
+
+impl Opt {
+    /// Should the document be read from stdin?
+    pub fn reads_stdin(&self) -> bool {
+        self.stdin || (self.files.len() == 1 && self.files[0] == Path::new("-"))
+    }
+
+    /// Directory from which the config file should be searched.
+    pub fn config_dir(&self) -> Res<PathBuf> {
+        let current_dir = current_dir()?;
+
+        match &self.stdin_filepath {
+            Some(path) if self.reads_stdin() => Ok(current_dir
+                .join(path)
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or(current_dir)),
+            _ => Ok(current_dir),
+        }
+    }
+
+    /// Directory of the config file, from which relative paths in the config are
+    /// resolved. It is the current directory if there is no config file.
+    pub fn config_root(&self) -> Res<PathBuf> {
+        let path = match &self.config {
+            Some(path) => Some(current_dir()?.join(path)),
+            None => Config::locate(self.config_dir()?),
+        };
+
+        match path {
+            Some(mut path) => {
+                path.pop();
+                Ok(path)
+            }
+            None => current_dir(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
 pub struct GenericConfig<Keys> {
+    /// Built-in settings, which the other settings extend or override.
+    #[serde(default)]
+    pub preset: Option<Preset>,
+
     /// Important keys in non-inline tables.
     /// Will be sorted first, then any non-important keys will be
-    /// sorted lexicographically.
+    /// sorted according to `sort_mode`.
     #[serde(default)]
     pub keys: Keys,
 
     /// Important keys in inline tables.
     /// Will be sorted first, then any non-important keys will be
-    /// sorted lexicographically.
+    /// sorted according to `sort_mode`.
     #[serde(default)]
     pub inline_keys: Keys,
 
     /// Does it sort arrays?
-    /// In case of mixed types, string will be ordered first, then
-    /// other values in original order.
+    /// Values are sorted using `compare_values`: in case of mixed types,
+    /// strings will be ordered first, then numbers, booleans, datetimes,
+    /// arrays and inline tables.
     #[serde(default)]
     pub sort_arrays: bool,
 
+    /// Dotted path patterns of the arrays that should be sorted, even if
+    /// `sort_arrays` is disabled (for exemple `features.*` or
+    /// `dependencies.*.features`). Other arrays are kept in original order.
+    #[serde(default)]
+    pub sorted_arrays: Vec<KeyPattern>,
+
+    /// Remove duplicate values in sorted arrays.
+    /// Comments of removed values are merged into the kept value.
+    #[serde(default)]
+    pub dedup_arrays: bool,
+
+    /// How keys (that are not important keys) and strings in sorted arrays
+    /// are compared.
+    #[serde(default)]
+    pub sort_mode: SortMode,
+
+    /// Key by which inline tables are sorted in sorted arrays.
+    /// If not provided, inline tables are kept in original order.
+    #[serde(default)]
+    pub inline_table_sort_key: Option<String>,
+
+    /// Which quotes are used for strings.
+    #[serde(default)]
+    pub quote_style: QuoteStyle,
+
+    /// Normalize the representation of numbers and datetimes, without
+    /// changing their values.
+    #[serde(default)]
+    pub normalize_literals: bool,
+
+    /// Dotted path patterns of the tables (inline or not) whose keys are kept
+    /// in original order. Their values are still formatted.
+    #[serde(default)]
+    pub unsorted_tables: Vec<KeyPattern>,
+
+    /// Dotted path patterns defining the order of table headers. Tables are
+    /// ordered by the first matching pattern, and tables not matching any
+    /// pattern come after. Sub-tables stay after their parent table.
+    #[serde(default)]
+    pub table_order: Vec<KeyPattern>,
+
+    /// Settings for tables matching a dotted path pattern.
+    /// The first matching rule providing `keys` replaces `keys` (or
+    /// `inline_keys` for inline tables) for that table, and the first one
+    /// providing `dotted_keys` replaces `dotted_keys`.
+    #[serde(default)]
+    pub tables: Vec<TableRule<Keys>>,
+
+    /// Indentation of multi-line arrays.
+    /// If not provided, it is read from the `.editorconfig` applying to the
+    /// formatted file, and defaults to a tab.
+    #[serde(default)]
+    pub indent: Option<Indent>,
+
+    /// Maximum width of `key = [...]` lines.
+    /// If provided, longer arrays are written with one value per line, while
+    /// shorter multi-line arrays without comments are written on a single line,
+    /// as well as arrays nested in arrays or inline tables.
+    /// Otherwise arrays are kept inline or multi-line as written.
+    #[serde(default)]
+    pub max_width: Option<usize>,
+
+    /// Convert inline tables whose `key = { ... }` line is longer than
+    /// `max_width` into sub-tables (`[parent.key]`).
+    #[serde(default)]
+    pub expand_inline_tables: bool,
+
+    /// Convert sub-tables written with their own header, containing only values
+    /// (other than inline tables) and no comments, into inline tables if their
+    /// `key = { ... }` line fits in `max_width`.
+    #[serde(default)]
+    pub collapse_tables: bool,
+
+    /// How dotted keys are normalized. Can be configured per table in `tables`.
+    #[serde(default)]
+    pub dotted_keys: DottedKeys,
+
+    /// Number of blank lines between sections of a table (at least 1, as blank
+    /// lines delimit sections). If not provided, blank lines are kept as written.
+    #[serde(default)]
+    pub blank_lines_between_sections: Option<usize>,
+
+    /// Number of blank lines before table headers.
+    /// If not provided, blank lines are kept as written.
+    #[serde(default)]
+    pub blank_lines_before_tables: Option<usize>,
+
+    /// Collapse runs of blank lines between comments into a single one.
+    #[serde(default)]
+    pub collapse_blank_lines: bool,
+
     #[serde(default)]
     /// Paths to ignore when scanning directories.
     pub excludes: Vec<String>,
+
+    /// Settings overriden for files matching some patterns.
+    #[serde(default)]
+    pub overrides: Vec<Override>,
+}
+
+/// Settings overriden for files matching `files`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Override {
+    /// Glob patterns of the files this override applies to, relative to the
+    /// folder containing the config file (for exemple `**/Cargo.toml`).
+    pub files: Vec<FilePattern>,
+
+    pub preset: Option<Option<Preset>>,
+    pub keys: Option<Vec<String>>,
+    pub inline_keys: Option<Vec<String>>,
+    pub sort_arrays: Option<bool>,
+    pub sorted_arrays: Option<Vec<KeyPattern>>,
+    pub dedup_arrays: Option<bool>,
+    pub sort_mode: Option<SortMode>,
+    pub inline_table_sort_key: Option<Option<String>>,
+    pub quote_style: Option<QuoteStyle>,
+    pub normalize_literals: Option<bool>,
+    pub unsorted_tables: Option<Vec<KeyPattern>>,
+    pub table_order: Option<Vec<KeyPattern>>,
+    pub tables: Option<Vec<TableRule<Vec<String>>>>,
+    pub indent: Option<Option<Indent>>,
+    pub max_width: Option<Option<usize>>,
+    pub expand_inline_tables: Option<bool>,
+    pub collapse_tables: Option<bool>,
+    pub dotted_keys: Option<DottedKeys>,
+    pub blank_lines_between_sections: Option<Option<usize>>,
+    pub blank_lines_before_tables: Option<Option<usize>>,
+    pub collapse_blank_lines: Option<bool>,
+}
+
+impl Override {
+    /// Does this override apply to the file at `path` (relative to the config file)?
+    pub fn matches(&self, path: &Path) -> bool {
+        self.files.iter().any(|pattern| pattern.matches(path))
+    }
+
+    /// Replace settings of `config` by the ones provided in this override.
+    pub fn apply(&self, config: &mut Config) {
+        macro_rules! apply {
+            ($($field:ident),*) => {
+                $(
+                    if let Some(x) = &self.$field {
+                        config.$field = x.clone();
+                    }
+                )*
+            };
+        }
+
+        apply!(
+            preset,
+            keys,
+            inline_keys,
+            sort_arrays,
+            sorted_arrays,
+            dedup_arrays,
+            sort_mode,
+            inline_table_sort_key,
+            quote_style,
+            normalize_literals,
+            unsorted_tables,
+            table_order,
+            tables,
+            indent,
+            max_width,
+            expand_inline_tables,
+            collapse_tables,
+            dotted_keys,
+            blank_lines_between_sections,
+            blank_lines_before_tables,
+            collapse_blank_lines
+        );
+    }
+}
+
+/// How dotted keys (`a.b = 1`) are normalized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DottedKeys {
+    /// Dotted keys and sub-tables are kept as written.
+    #[default]
+    Preserve,
+    /// Dotted keys are converted into sub-tables (`[a]` with `b = 1`).
+    Expand,
+    /// Sub-tables containing a single value are converted into dotted keys.
+    Collapse,
+}
+
+/// Rule applying to tables matching `path`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TableRule<Keys> {
+    /// Dotted path of the tables (inline or not) this rule applies to,
+    /// for exemple `package`, `dependencies.*` or `target.*.dependencies`.
+    pub path: KeyPattern,
+
+    /// Important keys in matching tables.
+    /// Will be sorted first, then any non-important keys will be
+    /// sorted according to `sort_mode`.
+    #[serde(default)]
+    pub keys: Option<Keys>,
+
+    /// How dotted keys of matching tables are normalized, replacing the
+    /// `dotted_keys` setting.
+    #[serde(default)]
+    pub dotted_keys: Option<DottedKeys>,
 }
 
 pub type Config = GenericConfig<Vec<String>>;
 pub type ProcessedConfig = GenericConfig<BTreeMap<String, usize>>;
 
 const CONFIG_FILE: &str = "toml-maid.toml";
+const STDIN: &str = "<stdin>";
 
 impl Config {
-    pub fn read_from_file() -> Option<Config> {
-        let mut path: PathBuf = std::env::current_dir().ok()?;
+    /// Search for a `toml-maid.toml` in the current directory and its parents.
+    /// Returns `None` if no config file is found, and an error if the found
+    /// config file is invalid.
+    pub fn read_from_file() -> Res<Option<Config>> {
+        Self::read_from_dir(current_dir()?)
+    }
+
+    /// Search for a `toml-maid.toml` in the provided directory and its parents.
+    /// Returns `None` if no config file is found, and an error if the found
+    /// config file is invalid.
+    pub fn read_from_dir(dir: impl AsRef<Path>) -> Res<Option<Config>> {
+        match Self::locate(dir) {
+            Some(path) => Ok(Some(Self::load(path)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Load the config file at the provided path.
+    pub fn load(path: impl AsRef<Path>) -> Res<Config> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+
+        toml::from_str(&text).map_err(|e| Error::config(path, &text, e))
+    }
+
+    /// Indices of the overrides applying to the file at `path` (relative to the
+    /// config file).
+    pub fn matching_overrides(&self, path: &Path) -> Vec<usize> {
+        self.overrides
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.matches(path))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Copy of this config with the overrides at the provided indices applied,
+    /// in order.
+    pub fn with_overrides(&self, indices: &[usize]) -> Config {
+        let mut config = self.clone();
+
+        for &i in indices {
+            self.overrides[i].apply(&mut config);
+        }
+
+        config
+    }
+
+    /// Find the path of the first `toml-maid.toml` in the provided directory
+    /// and its parents.
+    pub fn locate(dir: impl AsRef<Path>) -> Option<PathBuf> {
+        let mut path: PathBuf = dir.as_ref().to_path_buf();
         let filename = Path::new(CONFIG_FILE);
 
         loop {
             path.push(filename);
 
             if path.is_file() {
-                let text = std::fs::read_to_string(&path).ok()?;
-                let config: Self = toml::from_str(&text).ok()?;
-                return Some(config);
+                return Some(path);
             }
 
             if !(path.pop() && path.pop()) {
@@ -118,28 +670,445 @@ impl Config {
 }
 
 impl From<Config> for ProcessedConfig {
-    fn from(x: Config) -> Self {
-        let mut res = Self {
-            keys: BTreeMap::new(),
-            inline_keys: BTreeMap::new(),
+    fn from(mut x: Config) -> Self {
+        if let Some(preset) = x.preset {
+            preset.extend(&mut x);
+        }
+
+        Self {
+            preset: x.preset,
+            keys: index_keys(x.keys),
+            inline_keys: index_keys(x.inline_keys),
             sort_arrays: x.sort_arrays,
+            sorted_arrays: x.sorted_arrays,
+            dedup_arrays: x.dedup_arrays,
+            sort_mode: x.sort_mode,
+            inline_table_sort_key: x.inline_table_sort_key,
+            quote_style: x.quote_style,
+            normalize_literals: x.normalize_literals,
+            unsorted_tables: x.unsorted_tables,
+            table_order: x.table_order,
+            tables: x
+                .tables
+                .into_iter()
+                .map(|rule| TableRule {
+                    path: rule.path,
+                    keys: rule.keys.map(index_keys),
+                    dotted_keys: rule.dotted_keys,
+                })
+                .collect(),
+            indent: x.indent,
+            max_width: x.max_width,
+            expand_inline_tables: x.expand_inline_tables,
+            collapse_tables: x.collapse_tables,
+            dotted_keys: x.dotted_keys,
+            blank_lines_between_sections: x.blank_lines_between_sections,
+            blank_lines_before_tables: x.blank_lines_before_tables,
+            collapse_blank_lines: x.collapse_blank_lines,
             excludes: x.excludes,
+            overrides: x.overrides,
+        }
+    }
+}
+
+/// Value removed from an array by `dedup_arrays`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    /// Dotted path of the array.
+    pub path: String,
+    /// Removed value, formatted.
+    pub value: String,
+}
+
+/// Remove values equal (once formatted) to a previous value, merging their
+/// comments into the kept value. Values are pairs of (formatted, original) values.
+fn dedup_values(values: Vec<(Value, Value)>) -> Vec<(Value, Value)> {
+    let mut kept: Vec<(Value, Value)> = vec![];
+    let mut indices = BTreeMap::<String, usize>::new();
+
+    for (formatted, value) in values {
+        match indices.get(&undecorated(&formatted)) {
+            Some(&i) => merge_comments(&mut kept[i].1, &value),
+            None => {
+                indices.insert(undecorated(&formatted), kept.len());
+                kept.push((formatted, value));
+            }
+        }
+    }
+
+    kept
+}
+
+/// Display of a value without its surrounding decor.
+fn undecorated(value: &Value) -> String {
+    value.clone().decorated("", "").to_string()
+}
+
+/// Add the comments surrounding `removed` to the ones surrounding `kept`.
+fn merge_comments(kept: &mut Value, removed: &Value) {
+    let prefix_comments = comment_lines(removed.decor().prefix());
+    let suffix_comments = comment_lines(removed.decor().suffix());
+
+    if !prefix_comments.is_empty() {
+        // Comments are inserted on their own lines before the value, with the
+        // same indentation as the value.
+        let prefix = kept
+            .decor()
+            .prefix()
+            .map(|s| s.as_some_str())
+            .unwrap_or("")
+            .to_string();
+        let indent_start = prefix.rfind('\n').map(|i| i + 1).unwrap_or(prefix.len());
+        let (head, indent) = prefix.split_at(indent_start);
+
+        let mut new_prefix = head.to_string();
+        if !new_prefix.ends_with('\n') {
+            new_prefix.push('\n');
+        }
+        for comment in prefix_comments {
+            new_prefix.push_str(&format!("{}{}\n", indent, comment));
+        }
+        new_prefix.push_str(indent);
+
+        kept.decor_mut().set_prefix(new_prefix);
+    }
+
+    if !suffix_comments.is_empty() {
+        let mut new_suffix = kept
+            .decor()
+            .suffix()
+            .map(|s| s.as_some_str())
+            .unwrap_or("")
+            .to_string();
+
+        // Each comment stays on its own line.
+        if new_suffix.contains('#') && !new_suffix.ends_with('\n') {
+            new_suffix.push('\n');
+        }
+        for comment in suffix_comments {
+            new_suffix.push_str(&format!(" {}\n", comment));
+        }
+
+        kept.decor_mut().set_suffix(new_suffix);
+    }
+}
+
+/// Insert sorted entries of a section in `table`. The section prefix (blank
+/// lines and comments) is set on the first entry, or before the header of the
+/// first table if the section contains no values.
+fn insert_section(table: &mut Table, section: Vec<Entry<Item>>, section_decor: &Decor) {
+    for (i, mut entry) in section.into_iter().enumerate() {
+        // Add section prefix.
+        if i == 0 {
+            if let Some(prefix) = section_decor.prefix() {
+                let prefix = prefix.as_some_str();
+
+                match &mut entry.value {
+                    Item::Table(inner) if !inner.is_dotted() => prepend_prefix(inner, prefix),
+                    Item::ArrayOfTables(inner) => {
+                        if let Some(inner) = inner.get_mut(0) {
+                            prepend_prefix(inner, prefix);
+                        }
+                    }
+                    _ => entry.decor.set_prefix(prefix),
+                }
+            }
+        }
+
+        table.insert(&entry.key, entry.value);
+        *table.key_decor_mut(&entry.key).unwrap() = entry.decor;
+    }
+}
+
+/// Does the formatted table contain values displayed under its header?
+fn displays_values(table: &Table) -> bool {
+    table.iter().any(|(_, item)| match item {
+        Item::Value(_) => true,
+        Item::Table(inner) => inner.is_dotted(),
+        _ => false,
+    })
+}
+
+/// Is the table (once formatted as `formatted`) written with a header? Implicit
+/// tables get one when they contain values collapsed from their sub-tables, such
+/// that collapsing them again gives the same result as when formatting the output.
+fn has_header(table: &Table, formatted: &Table) -> bool {
+    !table.is_implicit() || displays_values(formatted)
+}
+
+/// Add comments of `prefix` before the header of `table`.
+fn prepend_prefix(table: &mut Table, prefix: &str) {
+    if !prefix.contains('#') {
+        return;
+    }
+
+    let header_prefix = table.decor().prefix().map_or("", |s| s.as_some_str());
+    let new_prefix = format!(
+        "{}\n{}",
+        prefix.trim_end_matches('\n'),
+        header_prefix.trim_start_matches('\n')
+    );
+    table.decor_mut().set_prefix(new_prefix);
+}
+
+/// Comment lines of a decor, trimmed.
+fn comment_lines(raw: Option<&RawString>) -> Vec<&str> {
+    raw.map(|s| s.as_some_str())
+        .unwrap_or("")
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('#'))
+        .collect()
+}
+
+/// Split the comments of a multi-line array so that each comment is attached
+/// to the value it describes and follows it when sorting.
+/// Returns the comments following the opening bracket, the values, and the
+/// text before the closing bracket.
+/// The prefix of each value contains the lines before it, while its suffix
+/// contains the comment following it on the same line (before or after the
+/// comma).
+fn attach_comments(array: &Array) -> (Vec<String>, Vec<Value>, String) {
+    // Split text following a comma in the comment on the same line and the
+    // next lines.
+    fn split(text: &str) -> (Vec<String>, String) {
+        let (first, rest) = match text.split_once('\n') {
+            Some((first, rest)) => (first, format!("\n{}", rest)),
+            None => (text, String::new()),
         };
 
-        for (i, key) in x.keys.iter().enumerate() {
-            res.keys.insert(key.clone(), i);
+        let comment = first.trim();
+        let comment = match comment.starts_with('#') {
+            true => vec![comment.to_string()],
+            false => vec![],
+        };
+
+        (comment, rest)
+    }
+
+    let mut opening = vec![];
+    let mut values: Vec<Value> = vec![];
+
+    let mut attach = |values: &mut Vec<Value>, comments: Vec<String>| match values.last_mut() {
+        Some(previous) => {
+            let mut suffix = previous
+                .decor()
+                .suffix()
+                .map(|s| s.as_some_str())
+                .unwrap_or("")
+                .to_string();
+            for comment in comments {
+                suffix.push_str(&format!(" {}\n", comment));
+            }
+            previous.decor_mut().set_suffix(suffix);
         }
+        None => opening.extend(comments),
+    };
+
+    // Lines following the comment after the previous value, before its comma.
+    let mut pending = String::new();
+
+    for value in array.iter() {
+        let (comments, prefix) = split(value.decor().prefix().map_or("", |s| s.as_some_str()));
+        attach(&mut values, comments);
+        let prefix = std::mem::take(&mut pending) + &prefix;
+
+        let (comments, rest) = split(value.decor().suffix().map_or("", |s| s.as_some_str()));
+        let suffix: String = comments
+            .iter()
+            .map(|comment| format!(" {}\n", comment))
+            .collect();
+        // The line break before the comma is followed by the one of the next
+        // prefix.
+        pending = match rest.rfind('\n') {
+            Some(end) => rest[..end].to_string(),
+            None => rest,
+        };
+
+        values.push(value.clone().decorated(prefix, suffix));
+    }
+
+    let (comments, closing) = split(array.trailing().as_some_str());
+    attach(&mut values, comments);
+    let closing = pending + &closing;
+
+    (opening, values, closing)
+}
 
-        for (i, key) in x.inline_keys.iter().enumerate() {
-            res.inline_keys.insert(key.clone(), i);
+/// Comments following a value in a multi-line array: the first one is written
+/// after the comma, and the others on their own lines.
+fn trailing_comments(comments: &[String], indent: Indent) -> String {
+    comments
+        .iter()
+        .enumerate()
+        .map(|(i, comment)| match i {
+            0 => format!(" {}", comment),
+            _ => format!("\n{}{}", indent, comment),
+        })
+        .collect()
+}
+
+/// Suffix of an array or inline table, keeping the comment following it.
+fn value_suffix(decor: &Decor, last: bool) -> String {
+    let comment = decor.suffix().map_or("", |s| s.as_some_str()).trim();
+
+    match (comment.is_empty(), last) {
+        (false, _) => format!(" {}", comment),
+        (true, true) => " ".to_string(),
+        (true, false) => "".to_string(),
+    }
+}
+
+/// Convert an inline table into a standard table, with default decors.
+fn to_table(inline: &InlineTable) -> Table {
+    let mut table = Table::new();
+
+    for (key, value) in inline.iter() {
+        table.insert(key, Item::Value(value.clone()));
+    }
+
+    table
+}
+
+/// Does the table (or its values) contain comments?
+fn table_has_comments(table: &Table) -> bool {
+    let has_comment = |decor: &Decor| {
+        [decor.prefix(), decor.suffix()]
+            .into_iter()
+            .flatten()
+            .any(|s| s.as_some_str().contains('#'))
+    };
+
+    has_comment(table.decor())
+        || table.iter().any(|(key, item)| {
+            table.key_decor(key).is_some_and(has_comment)
+                || match item.as_value() {
+                    Some(Value::Array(array)) => has_comment(array.decor()) || has_comments(array),
+                    Some(value) => has_comment(value.decor()),
+                    None => false,
+                }
+        })
+}
+
+/// Was the array written on multiple lines?
+fn is_multiline(array: &Array) -> bool {
+    array.trailing().as_some_str().starts_with('\n')
+        || array.iter().any(|item| {
+            let decor = item.decor();
+            [decor.prefix(), decor.suffix()]
+                .into_iter()
+                .flatten()
+                .any(|s| s.as_some_str().contains('\n'))
+        })
+}
+
+/// Indent the lines of the multi-line arrays in a value nested in a multi-line
+/// array, either directly or in inline tables.
+fn indent_nested_value(value: &mut Value, indent: Indent) {
+    match value {
+        Value::Array(nested) => indent_nested_array(nested, indent),
+        Value::InlineTable(table) => {
+            for (_, value) in table.iter_mut() {
+                indent_nested_value(value, indent);
+            }
         }
+        _ => (),
+    }
+}
 
-        res
+/// Indent the lines of a multi-line array nested in another multi-line array.
+fn indent_nested_array(array: &mut Array, indent: Indent) {
+    fn shift(text: &str, indent: Indent) -> String {
+        let last = text.matches('\n').count();
+
+        text.split('\n')
+            .enumerate()
+            .map(|(i, line)| match line {
+                // Keep blank lines empty.
+                _ if i == 0 || (line.is_empty() && i != last) => line.to_string(),
+                line => format!("{}{}", indent, line),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
     }
+
+    array.set_trailing(shift(array.trailing().as_some_str(), indent));
+
+    for value in array.iter_mut() {
+        indent_nested_value(value, indent);
+
+        let decor = value.decor_mut();
+        let prefix = shift(decor.prefix().map_or("", |s| s.as_some_str()), indent);
+        let suffix = shift(decor.suffix().map_or("", |s| s.as_some_str()), indent);
+        decor.set_prefix(prefix);
+        decor.set_suffix(suffix);
+    }
+}
+
+/// Does the array contain comments?
+fn has_comments(array: &Array) -> bool {
+    array.trailing().as_some_str().contains('#')
+        || array.iter().any(|item| {
+            let decor = item.decor();
+            [decor.prefix(), decor.suffix()]
+                .into_iter()
+                .flatten()
+                .any(|s| s.as_some_str().contains('#'))
+        })
+}
+
+/// Indent the lines of `text` (comments in a multi-line array) with `indent`,
+/// except the first one which follows the previous value.
+fn reindent(text: &str, indent: Indent) -> String {
+    text.split('\n')
+        .enumerate()
+        .map(|(i, line)| match line.trim_start() {
+            "" => "".to_string(),
+            _ if i == 0 => line.to_string(),
+            line => format!("{}{}", indent, line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Map each key to its position in the list.
+fn index_keys(keys: Vec<String>) -> BTreeMap<String, usize> {
+    keys.into_iter()
+        .enumerate()
+        .map(|(i, key)| (key, i))
+        .collect()
+}
+
+/// Path of a child entry.
+fn child_path(path: &[String], key: &str) -> Vec<String> {
+    let mut path = path.to_vec();
+    path.push(key.to_string());
+    path
+}
+
+fn compile_excludes(excludes: &[String]) -> Res<Vec<glob::Pattern>> {
+    excludes
+        .iter()
+        .map(|v| {
+            glob::Pattern::new(v).map_err(|e| Error::Config {
+                path: None,
+                line: None,
+                column: None,
+                message: format!("invalid pattern \"{}\" in 'excludes': {}", v, e),
+            })
+        })
+        .collect()
+}
+
+fn current_dir() -> Res<PathBuf> {
+    std::env::current_dir().map_err(|e| Error::io(".", e))
 }
 
-fn absolute_path(path: impl AsRef<Path>) -> Res<String> {
-    Ok(std::fs::canonicalize(&path)?.to_string_lossy().to_string())
+fn absolute_path(path: impl AsRef<Path>) -> String {
+    std::fs::canonicalize(&path)
+        .unwrap_or_else(|_| path.as_ref().to_path_buf())
+        .to_string_lossy()
+        .to_string()
 }
 
 pub fn find_files_recursively(
@@ -147,7 +1116,7 @@ pub fn find_files_recursively(
     extension: &str,
     verbose: bool,
     excludes: &[String],
-) -> Vec<PathBuf> {
+) -> Res<Vec<PathBuf>> {
     macro_rules! continue_on_err {
         ($in:expr, $context:expr) => {
             match $in {
@@ -167,10 +1136,7 @@ pub fn find_files_recursively(
     let extension: OsString = extension.into();
     let config_file: OsString = CONFIG_FILE.into();
 
-    let excludes: Vec<_> = excludes
-        .iter()
-        .map(|v| glob::Pattern::new(&v).expect("invalid pattern in 'excludes'"))
-        .collect();
+    let excludes = compile_excludes(excludes)?;
 
     for entry in ignore::WalkBuilder::new(&dir_path)
         .skip_stdout(true)
@@ -181,7 +1147,7 @@ pub fn find_files_recursively(
                 .expect("scanned file should be inside scanned dir");
 
             for exclude in &excludes {
-                if exclude.matches_path(&relative_path) {
+                if exclude.matches_path(relative_path) {
                     return false;
                 }
             }
@@ -214,58 +1180,265 @@ pub fn find_files_recursively(
         matches.push(path);
     }
 
-    matches
+    Ok(matches)
 }
 
 impl ProcessedConfig {
-    /// Process the provided file.
-    pub fn process_file(&self, path: impl AsRef<Path>, check: bool, verbose: bool) -> Res<()> {
-        let absolute_path = absolute_path(&path)?;
-        let text = std::fs::read_to_string(&path).unwrap_or_else(|e| {
-            eprintln!(
-                "Error while reading file \"{}\" : {}",
-                absolute_path,
-                e.to_string().red()
-            );
-            std::process::exit(3);
-        });
+    /// Is the provided path excluded by `excludes`? Patterns are relative to `root`.
+    pub fn excludes(&self, path: &Path, root: &Path) -> Res<bool> {
+        let path = current_dir()?.join(path);
+        let relative_path = path.strip_prefix(root).unwrap_or(&path);
+
+        Ok(compile_excludes(&self.excludes)?
+            .iter()
+            .any(|exclude| exclude.matches_path(relative_path)))
+    }
 
-        let doc = text.parse::<Document>()?;
-        let trailing = doc.trailing().as_some_str().trim_end();
+    /// Copy of this config using the indentation of the `.editorconfig` applying
+    /// to the file at `path`, if `indent` is not provided.
+    pub fn with_editorconfig(&self, path: &Path) -> Cow<'_, Self> {
+        if self.indent.is_some() {
+            return Cow::Borrowed(self);
+        }
 
-        let output_table = self.format_table(&doc)?;
-        let mut output_doc: Document = output_table.into();
-        output_doc.set_trailing(trailing); // Insert back trailing content (comments).
-        let output_text = format!("{}\n", output_doc.to_string().trim());
+        match editorconfig::indent(path) {
+            Some(indent) => Cow::Owned(Self {
+                indent: Some(indent),
+                ..self.clone()
+            }),
+            None => Cow::Borrowed(self),
+        }
+    }
+
+    /// Process the provided file.
+    pub fn process_file(
+        &self,
+        path: impl AsRef<Path>,
+        check: bool,
+        diff: bool,
+        verbose: bool,
+    ) -> Res<Outcome> {
+        let path = path.as_ref();
+        let absolute_path = absolute_path(path);
+        let text = std::fs::read_to_string(path).map_err(|e| Error::io(&absolute_path, e))?;
+
+        let output_text = self
+            .format_str(&text)
+            .map_err(|e| Error::format(&absolute_path, &text, e))?;
+
+        if verbose && self.dedup_arrays {
+            if let Ok(doc) = text.parse::<Document>() {
+                for duplicate in self.find_duplicates(&doc) {
+                    println!(
+                        "Removed duplicate in {}: {} = {}",
+                        absolute_path,
+                        duplicate.path,
+                        duplicate.value.yellow()
+                    );
+                }
+            }
+        }
 
         if check {
             if text != output_text {
                 eprintln!("Check fails : {}", absolute_path.red());
-                std::process::exit(2);
-            } else if verbose {
-                println!("Check succeed: {}", absolute_path.green());
+                if diff {
+                    print!(
+                        "{}",
+                        unified_diff(&text, &output_text, &absolute_path, &absolute_path)
+                    );
+                }
+                Ok(Outcome::WouldReformat)
+            } else {
+                if verbose {
+                    println!("Check succeed: {}", absolute_path.green());
+                }
+                Ok(Outcome::Unchanged)
             }
         } else if text != output_text {
-            let mut file = File::create(&path)?;
-            file.write_all(output_text.as_bytes())?;
-            file.flush()?;
+            let write = || -> std::io::Result<()> {
+                let mut file = File::create(path)?;
+                file.write_all(output_text.as_bytes())?;
+                file.flush()
+            };
+            write().map_err(|e| Error::io(&absolute_path, e))?;
+
             if verbose {
                 println!("Overwritten: {}", absolute_path.blue());
             }
-        } else if verbose {
-            println!("Unchanged: {}", absolute_path.green());
+            Ok(Outcome::Reformatted)
+        } else {
+            if verbose {
+                println!("Unchanged: {}", absolute_path.green());
+            }
+            Ok(Outcome::Unchanged)
+        }
+    }
+
+    /// Process a document read from stdin, and print the result in stdout.
+    /// `path` is used to check if the document is excluded (relative to `root`),
+    /// in which case it is printed unchanged.
+    pub fn process_stdin(
+        &self,
+        path: Option<&Path>,
+        root: &Path,
+        check: bool,
+        diff: bool,
+    ) -> Res<Outcome> {
+        let name = path.unwrap_or_else(|| Path::new(STDIN));
+
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| Error::io(name, e))?;
+
+        let excluded = match path {
+            Some(path) => self.excludes(path, root)?,
+            None => false,
+        };
+
+        let output_text = if excluded {
+            text.clone()
+        } else {
+            self.format_str(&text)
+                .map_err(|e| Error::format(name, &text, e))?
+        };
+
+        if check {
+            if text != output_text {
+                let name = name.to_string_lossy();
+                eprintln!("Check fails : {}", name.red());
+                if diff {
+                    print!("{}", unified_diff(&text, &output_text, &name, &name));
+                }
+                return Ok(Outcome::WouldReformat);
+            }
+        } else {
+            let mut stdout = std::io::stdout();
+            stdout
+                .write_all(output_text.as_bytes())
+                .and_then(|_| stdout.flush())
+                .map_err(|e| Error::io(name, e))?;
+        }
+
+        Ok(if text != output_text {
+            Outcome::Reformatted
+        } else {
+            Outcome::Unchanged
+        })
+    }
+
+    /// Format the text of a TOML document.
+    /// Performs no I/O, which allows to use the formatter as a library.
+    pub fn format_str(&self, input: &str) -> Result<String, FormatError> {
+        let doc = input.parse::<Document>()?;
+        let output_doc = self.format_document(&doc);
+        Ok(format!("{}\n", output_doc.to_string().trim()))
+    }
+
+    /// Format a parsed TOML document.
+    pub fn format_document(&self, doc: &Document) -> Document {
+        let trailing = doc.trailing().as_some_str().trim_end();
+
+        let output_table = self.format_table(doc, &[]);
+        let mut output_doc: Document = output_table.into();
+        // Insert back trailing content (comments).
+        output_doc.set_trailing(self.normalize_prefix(trailing, None));
+        output_doc
+    }
+
+    /// Important keys of the table (inline or not) at the provided path.
+    fn important_keys(&self, path: &[String], inline: bool) -> &BTreeMap<String, usize> {
+        let keys = self
+            .tables
+            .iter()
+            .filter(|rule| rule.path.matches(path))
+            .find_map(|rule| rule.keys.as_ref());
+
+        match keys {
+            Some(keys) => keys,
+            None if inline => &self.inline_keys,
+            None => &self.keys,
+        }
+    }
+
+    /// Values that are removed from sorted arrays by `dedup_arrays`.
+    pub fn find_duplicates(&self, doc: &Document) -> Vec<Duplicate> {
+        let mut duplicates = vec![];
+
+        if self.dedup_arrays {
+            self.find_duplicates_in_table(doc, &[], &mut duplicates);
+        }
+
+        duplicates
+    }
+
+    fn find_duplicates_in_table(&self, table: &Table, path: &[String], out: &mut Vec<Duplicate>) {
+        for (key, item) in table.iter() {
+            let path = child_path(path, key);
+
+            match item {
+                Item::None => (),
+                Item::Value(value) => self.find_duplicates_in_value(value, &path, out),
+                Item::Table(table) => self.find_duplicates_in_table(table, &path, out),
+                Item::ArrayOfTables(array) => {
+                    for table in array.iter() {
+                        self.find_duplicates_in_table(table, &path, out);
+                    }
+                }
+            }
+        }
+    }
+
+    fn find_duplicates_in_value(&self, value: &Value, path: &[String], out: &mut Vec<Duplicate>) {
+        match value {
+            Value::Array(array) => {
+                if self.sorts_array(path) {
+                    let mut seen = BTreeSet::new();
+
+                    for value in array.iter() {
+                        let display = undecorated(&self.format_nested_value(value, path, false));
+
+                        if !seen.insert(display.clone()) {
+                            out.push(Duplicate {
+                                path: path.join("."),
+                                value: display,
+                            });
+                        }
+                    }
+                }
+
+                for value in array.iter() {
+                    self.find_duplicates_in_value(value, path, out);
+                }
+            }
+            Value::InlineTable(table) => {
+                for (key, value) in table.iter() {
+                    self.find_duplicates_in_value(value, &child_path(path, key), out);
+                }
+            }
+            _ => (),
         }
+    }
 
-        Ok(())
+    /// Should the array at the provided path be sorted?
+    fn sorts_array(&self, path: &[String]) -> bool {
+        self.sort_arrays || self.sorted_arrays.iter().any(|p| p.matches(path))
     }
 
-    /// Format a `Table`.
+    /// Should the keys of the table (inline or not) at the provided path be sorted?
+    fn sorts_keys(&self, path: &[String]) -> bool {
+        !self.unsorted_tables.iter().any(|p| p.matches(path))
+    }
+
+    /// Format a `Table` located at `path`.
     /// Consider empty lines as "sections" and will not sort accross sections.
     /// Comments at the start of the section will stay at the start, while
     /// comments attached to any other line will stay attached to that line.
-    fn format_table(&self, table: &Table) -> Res<Table> {
+    fn format_table(&self, table: &Table, path: &[String]) -> Table {
         let mut formated_table = Table::new();
         formated_table.set_implicit(true); // avoid empty `[dotted.keys]`
+        formated_table.set_dotted(table.is_dotted());
         let prefix = table
             .decor()
             .prefix()
@@ -276,27 +1449,61 @@ impl ProcessedConfig {
             .suffix()
             .map(|s| s.as_some_str())
             .unwrap_or("");
+        let prefix = match path.is_empty() || table.is_dotted() {
+            true => prefix.to_string(),
+            false => self.normalize_prefix(prefix, self.blank_lines_before_tables),
+        };
         formated_table.decor_mut().set_prefix(prefix);
         formated_table.decor_mut().set_suffix(suffix);
 
         let mut section_decor = Decor::default();
         let mut section = Vec::<Entry<Item>>::new();
 
+        let keys = self.important_keys(path, false);
+        let sorted = self.sorts_keys(path);
+
+        // Values are displayed before sub-tables, which are ordered according
+        // to `table_order`.
+        let table_rank = |entry: &Entry<Item>| match &entry.value {
+            Item::Table(table) if table.is_dotted() => None,
+            Item::Table(_) | Item::ArrayOfTables(_) => {
+                Some(self.table_rank(&child_path(path, &entry.key)))
+            }
+            _ => None,
+        };
+
         let sort = |x: &Entry<Item>, y: &Entry<Item>| {
-            let xord = self.keys.get(&x.key);
-            let yord = self.keys.get(&y.key);
+            match table_rank(x).cmp(&table_rank(y)) {
+                Ordering::Equal => (),
+                ord => return ord,
+            }
+
+            // Stable sort will keep original order.
+            if !sorted {
+                return Ordering::Equal;
+            }
+
+            let xord = keys.get(&x.key);
+            let yord = keys.get(&y.key);
 
             match (xord, yord) {
                 (Some(_), None) => Ordering::Less,
                 (None, Some(_)) => Ordering::Greater,
                 (Some(x), Some(y)) => x.cmp(y),
-                (None, None) => x.key.cmp(&y.key),
+                (None, None) => self.sort_mode.compare(&x.key, &y.key),
             }
         };
 
         // Iterate over all original entries.
+        // Keys are inserted back from their unquoted name, which makes them bare
+        // when possible and quoted otherwise.
         for (i, (key, item)) in table.iter().enumerate() {
-            let mut key_decor = table.key_decor(key).unwrap().clone();
+            let mut key_decor = match item {
+                // Decor of sub-tables keys is displayed inside headers.
+                Item::Table(inner) if !inner.is_dotted() => Decor::default(),
+                Item::ArrayOfTables(_) => Decor::default(),
+                _ => table.key_decor(key).unwrap().clone(),
+            };
 
             // First entry can be decored (prefix).
             // In that case we want to keep that decoration at the start of the section.
@@ -304,7 +1511,7 @@ impl ProcessedConfig {
                 if let Some(prefix) = key_decor.prefix() {
                     let prefix = prefix.as_some_str();
                     if !prefix.is_empty() {
-                        section_decor.set_prefix(prefix);
+                        section_decor.set_prefix(self.normalize_prefix(prefix, None));
                         key_decor.set_prefix("".to_string());
                     }
                 }
@@ -317,28 +1524,25 @@ impl ProcessedConfig {
                 if prefix.starts_with('\n') {
                     // Sort keys and insert them.
                     section.sort_by(sort);
-
-                    for (i, mut entry) in section.into_iter().enumerate() {
-                        // Add section prefix.
-                        if i == 0 {
-                            if let Some(prefix) = section_decor.prefix() {
-                                let prefix = prefix.as_some_str();
-                                entry.decor.set_prefix(prefix);
-                            }
-                        }
-
-                        formated_table.insert(&entry.key, entry.value);
-                        *formated_table.key_decor_mut(&entry.key).unwrap() = entry.decor;
-                    }
+                    insert_section(&mut formated_table, section, &section_decor);
 
                     // Cleanup for next sections.
                     section = Vec::new();
                     section_decor = Decor::default();
-                    section_decor.set_prefix(prefix);
+                    section_decor.set_prefix(self.normalize_prefix(
+                        prefix,
+                        self.blank_lines_between_sections.map(|n| n.max(1)),
+                    ));
                     key_decor.set_prefix("".to_string());
                 }
             }
 
+            // Comments before keys inside sections.
+            if let Some(prefix) = key_decor.prefix() {
+                let prefix = self.normalize_prefix(prefix.as_some_str(), None);
+                key_decor.set_prefix(prefix);
+            }
+
             // Remove any trailing newline in decor suffix.
             if let Some(suffix) = key_decor.suffix().map(|x| x.to_owned()) {
                 let suffix = suffix.as_some_str();
@@ -346,12 +1550,64 @@ impl ProcessedConfig {
             }
 
             // Format inner item.
+            let item_path = child_path(path, key);
             let new_item = match item {
                 Item::None => Item::None,
-                Item::Value(inner) => Item::Value(self.format_value(inner, false)?),
-                Item::Table(inner) => Item::Table(self.format_table(inner)?),
-                // TODO : Doesn't seem we have any of those.
-                Item::ArrayOfTables(inner) => Item::ArrayOfTables(inner.clone()),
+                Item::Value(Value::InlineTable(inner))
+                    if self.expands_inline_table(&item_path, inner) =>
+                {
+                    let mut new_table = self.format_table(&to_table(inner), &item_path);
+
+                    // Comments around the inline table are moved around the header.
+                    self.set_header_comments(&mut new_table, &key_decor);
+
+                    let comment = inner
+                        .decor()
+                        .suffix()
+                        .map_or("", |s| s.as_some_str())
+                        .trim();
+                    if !comment.is_empty() {
+                        new_table.decor_mut().set_suffix(format!(" {}", comment));
+                    }
+
+                    // Key decor is displayed inside the header.
+                    key_decor = Decor::default();
+                    Item::Table(new_table)
+                }
+                Item::Value(inner) => Item::Value(self.format_value(inner, &item_path, false)),
+                Item::Table(inner) if inner.is_dotted() => {
+                    let mut new_table = self.format_table(inner, &item_path);
+
+                    if self.dotted_keys(path) == DottedKeys::Expand {
+                        new_table.set_dotted(false);
+
+                        // Comments before the dotted keys are moved before the header.
+                        self.set_header_comments(&mut new_table, &key_decor);
+                        key_decor = Decor::default();
+                    }
+
+                    Item::Table(new_table)
+                }
+                Item::Table(inner) => {
+                    let mut new_table = self.format_table(inner, &item_path);
+
+                    if self.collapses_to_dotted_keys(path, inner, &new_table) {
+                        new_table.set_dotted(true);
+                        new_table.decor_mut().clear();
+                        Item::Table(new_table)
+                    } else {
+                        match self.collapsed_table(path, key, inner, &new_table) {
+                            Some(inline) => {
+                                key_decor = Decor::default();
+                                Item::Value(Value::InlineTable(inline))
+                            }
+                            None => Item::Table(new_table),
+                        }
+                    }
+                }
+                Item::ArrayOfTables(inner) => {
+                    Item::ArrayOfTables(self.format_array_of_tables(inner, &item_path))
+                }
             };
 
             section.push(Entry {
@@ -363,52 +1619,249 @@ impl ProcessedConfig {
 
         // End of entries, we insert remaining section.
         section.sort_by(sort);
+        insert_section(&mut formated_table, section, &section_decor);
+
+        // An implicit table which now contains values (collapsed from sub-tables)
+        // gets a header, separated from the previous content.
+        if table.is_implicit()
+            && displays_values(&formated_table)
+            && !path.is_empty()
+            && !table.is_dotted()
+        {
+            let prefix = formated_table
+                .decor()
+                .prefix()
+                .map_or("", |s| s.as_some_str());
+            if !prefix.contains('\n') {
+                let prefix =
+                    self.normalize_prefix(&format!("\n{}", prefix), self.blank_lines_before_tables);
+                formated_table.decor_mut().set_prefix(prefix);
+            }
+        }
 
-        for (i, mut entry) in section.into_iter().enumerate() {
-            // Add section prefix.
-            if i == 0 {
-                if let Some(prefix) = section_decor.prefix() {
-                    let prefix = prefix.as_some_str();
-                    entry.decor.set_prefix(prefix);
-                }
+        formated_table
+    }
+
+    /// Is the inline table in the table at `path` kept as dotted keys?
+    fn keeps_dotted_inline_table(&self, path: &[String], table: &InlineTable) -> bool {
+        table.is_dotted() && self.dotted_keys(path) != DottedKeys::Expand
+    }
+
+    /// How dotted keys of the table at `path` are normalized.
+    fn dotted_keys(&self, path: &[String]) -> DottedKeys {
+        self.tables
+            .iter()
+            .filter(|rule| rule.path.matches(path))
+            .find_map(|rule| rule.dotted_keys)
+            .unwrap_or(self.dotted_keys)
+    }
+
+    /// Should the sub-table of the table at `path` (once formatted as
+    /// `formatted`) be written as dotted keys? With `DottedKeys::Collapse`, it
+    /// is the case for sub-tables (not directly in the document root) written
+    /// with their own header, with a single value and no comments.
+    fn collapses_to_dotted_keys(&self, path: &[String], table: &Table, formatted: &Table) -> bool {
+        // Implicit tables would be replaced by dotted keys under a header that
+        // was never written, unless they get one in this pass.
+        if path.is_empty()
+            || !has_header(table, formatted)
+            || self.dotted_keys(path) != DottedKeys::Collapse
+        {
+            return false;
+        }
+
+        // Tables containing dotted keys are kept, such that collapsing is not
+        // repeated with the parent table when formatting again.
+        let mut items = formatted.iter();
+        let single = matches!(
+            (items.next(), items.next()),
+            (Some((_, Item::Value(_))), None)
+        );
+
+        single && !table_has_comments(formatted)
+    }
+
+    /// Set the prefix of a table converted from a value (inline table or dotted
+    /// keys) to the comments that were before the key.
+    fn set_header_comments(&self, table: &mut Table, key_decor: &Decor) {
+        let comments = key_decor.prefix().map_or("", |s| s.as_some_str()).trim();
+        let prefix = if comments.is_empty() {
+            "\n".to_string()
+        } else {
+            format!("\n{}\n", comments)
+        };
+
+        table
+            .decor_mut()
+            .set_prefix(self.normalize_prefix(&prefix, self.blank_lines_before_tables));
+    }
+
+    /// Position of the table at `path` in `table_order`, or the number of
+    /// patterns if it doesn't match any. A pattern matching sub-tables of the
+    /// table also applies to it, such that `target.*` moves the `target` table.
+    fn table_rank(&self, path: &[String]) -> usize {
+        self.table_order
+            .iter()
+            .position(|pattern| pattern.matches_prefix(path))
+            .unwrap_or(self.table_order.len())
+    }
+
+    /// Normalize the blank lines of a prefix (lines before a key or a header).
+    /// If provided, `blank_lines` replaces the number of blank lines at the start
+    /// of the prefix, and with `collapse_blank_lines` runs of blank lines between
+    /// comments are collapsed into a single one.
+    fn normalize_prefix(&self, prefix: &str, blank_lines: Option<usize>) -> String {
+        if blank_lines.is_none() && !self.collapse_blank_lines {
+            return prefix.to_string();
+        }
+
+        // The last line is the text before the key on the same line.
+        let (lines, last) = match prefix.rsplit_once('\n') {
+            Some((lines, last)) => (lines.split('\n').collect(), last),
+            None => (vec![], prefix),
+        };
+
+        let leading = lines
+            .iter()
+            .take_while(|line| line.trim().is_empty())
+            .count();
+
+        let mut output = "\n".repeat(blank_lines.unwrap_or(leading));
+        let mut previous_blank = false;
+
+        for line in &lines[leading..] {
+            let blank = line.trim().is_empty();
+
+            if !(blank && previous_blank && self.collapse_blank_lines) {
+                output.push_str(line);
+                output.push('\n');
             }
 
-            formated_table.insert(&entry.key, entry.value);
-            *formated_table.key_decor_mut(&entry.key).unwrap() = entry.decor;
+            previous_blank = blank;
+        }
+
+        output.push_str(last);
+        output
+    }
+
+    /// Should the inline table at `path` be converted into a sub-table?
+    /// With `expand_inline_tables`, it is the case if its `key = { ... }` line
+    /// is longer than `max_width`.
+    fn expands_inline_table(&self, path: &[String], table: &InlineTable) -> bool {
+        match (self.max_width, path.last()) {
+            (Some(max_width), Some(key)) if self.expand_inline_tables => {
+                let inline = self.format_inline_table(table, path, false);
+                key.chars().count() + 3 + inline.to_string().trim().chars().count() > max_width
+            }
+            _ => false,
+        }
+    }
+
+    /// Inline version of the sub-table at `key` of the table at `path` (once
+    /// formatted as `formatted`), if it should be collapsed.
+    /// With `collapse_tables`, sub-tables (not directly in the document root)
+    /// written with their own header, containing only values (other than inline
+    /// tables) and no comments, are collapsed if their `key = { ... }` line
+    /// fits in `max_width`.
+    fn collapsed_table(
+        &self,
+        path: &[String],
+        key: &str,
+        table: &Table,
+        formatted: &Table,
+    ) -> Option<InlineTable> {
+        let max_width = self.max_width.filter(|_| self.collapse_tables)?;
+
+        // Implicit tables and tables containing (inline) sub-tables would be
+        // replaced by nested inline tables, under headers that were never written.
+        let own_header = has_header(table, formatted)
+            && formatted
+                .iter()
+                .all(|(_, item)| item.is_value() && !item.is_inline_table());
+
+        if path.is_empty() || !own_header || formatted.is_empty() || table_has_comments(formatted) {
+            return None;
+        }
+
+        let mut inline = InlineTable::new();
+        for (key, item) in formatted.iter() {
+            inline.insert(key, item.as_value()?.clone());
+        }
+
+        let inline = self.format_inline_table(&inline, &child_path(path, key), false);
+        let display = inline.to_string();
+        let width = key.chars().count() + 3 + display.trim().chars().count();
+
+        (!display.contains('\n') && width <= max_width).then_some(inline)
+    }
+
+    /// Format an array of tables (`[[table]]` entries).
+    /// Each table is formatted like a standard table, but the order of the tables
+    /// themselves is kept as it is usually meaningful.
+    fn format_array_of_tables(&self, array: &ArrayOfTables, path: &[String]) -> ArrayOfTables {
+        let mut formated_array = ArrayOfTables::new();
+
+        for table in array.iter() {
+            formated_array.push(self.format_table(table, path));
         }
 
-        Ok(formated_table)
+        formated_array
     }
 
     /// Format inline tables `{ key = value, key = value }`.
-    /// TOML doesn't seem to support inline comments, so we just override entries decors
-    /// to respect proper spaces.
-    pub fn format_inline_table(&self, table: &InlineTable, last: bool) -> Res<InlineTable> {
+    /// TOML 1.0 doesn't support comments inside inline tables (multi-line inline
+    /// tables from TOML 1.1 are rejected by the parser), so we just override
+    /// entries decors to respect proper spaces. A comment following the table
+    /// is kept.
+    pub fn format_inline_table(
+        &self,
+        table: &InlineTable,
+        path: &[String],
+        last: bool,
+    ) -> InlineTable {
         let mut formated_table = InlineTable::new();
-        if last {
-            formated_table.decor_mut().set_suffix(" ");
+        let parent = &path[..path.len().saturating_sub(1)];
+        let dotted = self.keeps_dotted_inline_table(parent, table);
+
+        // Dotted keys (`{ a.b = 1 }`) are written without braces.
+        formated_table.set_dotted(dotted);
+        if !dotted {
+            formated_table
+                .decor_mut()
+                .set_suffix(value_suffix(table.decor(), last));
         }
 
         let mut entries = Vec::<Entry<Value>>::new();
 
+        let keys = self.important_keys(path, true);
+        let sorted = self.sorts_keys(path);
         let sort = |x: &Entry<Value>, y: &Entry<Value>| {
-            let xord = self.inline_keys.get(&x.key);
-            let yord = self.inline_keys.get(&y.key);
+            // Stable sort will keep original order.
+            if !sorted {
+                return Ordering::Equal;
+            }
+
+            let xord = keys.get(&x.key);
+            let yord = keys.get(&y.key);
 
             match (xord, yord) {
                 (Some(_), None) => Ordering::Less,
                 (None, Some(_)) => Ordering::Greater,
                 (Some(x), Some(y)) => x.cmp(y),
-                (None, None) => x.key.cmp(&y.key),
+                (None, None) => self.sort_mode.compare(&x.key, &y.key),
             }
         };
 
         for (key, value) in table.iter() {
             let mut key_decor = table.key_decor(key).unwrap().clone();
 
-            // Trim decor.
-            key_decor.set_prefix(" ");
-            key_decor.set_suffix(" ");
+            // Trim decor. Keys of dotted keys are separated by dots only.
+            let followed_by_dot = match value {
+                Value::InlineTable(inner) => self.keeps_dotted_inline_table(path, inner),
+                _ => false,
+            };
+            key_decor.set_prefix(if dotted { "" } else { " " });
+            key_decor.set_suffix(if followed_by_dot { "" } else { " " });
 
             let new_value = value.clone();
 
@@ -423,20 +1876,37 @@ impl ProcessedConfig {
 
         let len = entries.len();
         for (i, entry) in entries.into_iter().enumerate() {
-            let new_value = self.format_value(&entry.value, i + 1 == len)?;
+            let entry_path = child_path(path, &entry.key);
+            // The last value of dotted keys is only followed by the closing
+            // brace if the dotted keys are last.
+            let last_value = i + 1 == len && (last || !dotted);
+            let new_value = self.format_nested_value(&entry.value, &entry_path, last_value);
 
             formated_table.insert(&entry.key, new_value);
             *formated_table.key_decor_mut(&entry.key).unwrap() = entry.decor;
         }
 
-        Ok(formated_table)
+        formated_table
     }
 
-    /// Format a `Value`.
-    pub fn format_value(&self, value: &Value, last: bool) -> Res<Value> {
-        Ok(match value {
-            Value::Array(inner) => Value::Array(self.format_array(inner, last)?),
-            Value::InlineTable(inner) => Value::InlineTable(self.format_inline_table(inner, last)?),
+    /// Format a `Value` located at `path`, which is the value of a table key.
+    pub fn format_value(&self, value: &Value, path: &[String], last: bool) -> Value {
+        self.format_value_in(value, path, last, true)
+    }
+
+    /// Format a `Value` located at `path` inside an array or an inline table.
+    fn format_nested_value(&self, value: &Value, path: &[String], last: bool) -> Value {
+        self.format_value_in(value, path, last, false)
+    }
+
+    /// Format a `Value` located at `path`. Only arrays which are the value of a
+    /// table key (`in_table`) are wrapped according to `max_width`.
+    fn format_value_in(&self, value: &Value, path: &[String], last: bool, in_table: bool) -> Value {
+        match value {
+            Value::Array(inner) => Value::Array(self.format_array(inner, path, last, in_table)),
+            Value::InlineTable(inner) => {
+                Value::InlineTable(self.format_inline_table(inner, path, last))
+            }
             v => {
                 let mut v = v.clone();
 
@@ -465,98 +1935,90 @@ impl ProcessedConfig {
                     format!(" {}", suffix)
                 };
 
-                // Convert simple '...' to "..."
-                // Doesn't modify strings starting with multiple ' as they
-                // could be multiline literals.
-                // Doesn't modify strings containing \ or "
-                let mut display = v.clone().decorated("", "").to_string();
-                if display.starts_with('\'')
-                    && !display.starts_with("''")
-                    && display.find(&['\\', '"'][..]).is_none()
-                {
-                    if let Some(s) = display.strip_prefix('\'') {
-                        display = s.to_string();
-                    }
+                // Convert quotes according to `quote_style`.
+                if let Some(requoted) = self.quote_style.requote(&v) {
+                    v = requoted;
+                }
 
-                    if let Some(s) = display.strip_suffix('\'') {
-                        display = s.to_string();
+                if self.normalize_literals {
+                    if let Some(normalized) = normalize_literal(&v) {
+                        v = normalized;
                     }
-
-                    v = display.into();
                 }
 
                 // Handle surrounding spaces.
                 if last {
-                    v.decorated(&format!("{} ", prefix), &format!("{} ", suffix))
+                    v.decorated(format!("{} ", prefix), format!("{} ", suffix))
                 } else {
-                    v.decorated(&format!("{} ", prefix), &suffix.to_string())
+                    v.decorated(format!("{} ", prefix), suffix)
                 }
             }
-        })
+        }
     }
 
     /// Format an `Array`.
     /// Detect if the array is inline or multi-line, and format it accordingly.
-    /// Support comments in multi-line arrays.
-    /// With config `sort_string_arrays` the array String entries will be sorted, otherwise will be kept
-    /// as is.
-    fn format_array(&self, array: &Array, last: bool) -> Res<Array> {
-        let mut values: Vec<_> = array.iter().cloned().collect();
-
-        if self.sort_arrays {
-            values.sort_by(|x, y| match (x, y) {
-                (Value::String(x), Value::String(y)) => x.value().cmp(y.value()),
-                (Value::String(_), _) => Ordering::Less,
-                (_, Value::String(_)) => Ordering::Greater,
-                (_, _) => Ordering::Equal,
-            });
+    /// Support comments in multi-line arrays: comments on the lines before a value
+    /// stay attached to it when sorting, and comments following a value are
+    /// written after its comma.
+    /// With config `sort_arrays`, or if `path` matches one of `sorted_arrays`, the
+    /// array entries will be sorted (and deduplicated with `dedup_arrays`),
+    /// otherwise will be kept as is.
+    fn format_array(&self, array: &Array, path: &[String], last: bool, in_table: bool) -> Array {
+        let (opening, mut values, closing) = attach_comments(array);
+
+        if self.sorts_array(path) {
+            // Values are compared once formatted, as nested arrays could be sorted.
+            let key = self.inline_table_sort_key.as_deref();
+            let mut sortable: Vec<_> = values
+                .into_iter()
+                .map(|value| (self.format_nested_value(&value, path, false), value))
+                .collect();
+
+            sortable.sort_by(|(x, _), (y, _)| compare_values(x, y, self.sort_mode, key));
+
+            if self.dedup_arrays {
+                sortable = dedup_values(sortable);
+            }
+
+            values = sortable.into_iter().map(|(_, value)| value).collect();
         }
 
+        let indent = self.indent.unwrap_or_default();
         let mut new_array = Array::new();
 
         for value in values.into_iter() {
             new_array.push_formatted(value);
         }
 
-        let mut multiline = array.trailing().as_some_str().starts_with('\n');
-        if !multiline {
-            for item in array.iter() {
-                if let Some(prefix) = item.decor().prefix() {
-                    if prefix.as_some_str().contains('\n') {
-                        multiline = true;
-                        break;
-                    }
-                }
-
-                if let Some(suffix) = item.decor().suffix() {
-                    if suffix.as_some_str().contains('\n') {
-                        multiline = true;
-                        break;
-                    }
-                }
+        // Comments following the opening bracket or before the closing one.
+        let bracket_comments = !opening.is_empty() || closing.contains('#');
+
+        let multiline = match self.max_width {
+            _ if bracket_comments => true,
+            // Arrays with comments cannot be inlined.
+            _ if has_comments(array) => is_multiline(array),
+            // Only arrays which are the value of a table key are wrapped, such
+            // that arrays nested in arrays or inline tables stay inline.
+            Some(max_width) if in_table && !new_array.is_empty() => {
+                let mut inline = new_array.clone();
+                self.layout_inline_array(&mut inline, path);
+
+                let key_width = path.last().map_or(0, |key| key.chars().count() + 3);
+                key_width + inline.to_string().trim().chars().count() > max_width
             }
-        }
+            Some(_) => false,
+            None => is_multiline(array),
+        };
 
         // Multiline array
         if multiline {
-            let mut trailing = format!(
-                "{}\n",
-                array
-                    .trailing()
-                    .as_some_str()
-                    .trim_matches(&[' ', '\t'][..])
-                    .trim_end()
-            );
-
-            if !trailing.starts_with('\n') {
-                trailing = format!(" {trailing}");
-            }
+            // Comments following the previous value (or the opening bracket),
+            // written after its comma.
+            let mut comments = opening;
 
-            new_array.set_trailing(&trailing);
-            new_array.set_trailing_comma(true);
-
-            for value in new_array.iter_mut() {
-                let prefix = value
+            for (i, value) in new_array.iter_mut().enumerate() {
+                let leading = value
                     .decor()
                     .prefix()
                     .map(|s| s.as_some_str())
@@ -564,51 +2026,61 @@ impl ProcessedConfig {
                     .trim_matches(&[' ', '\t'][..])
                     .trim_end_matches('\n');
 
-                let mut prefix = if !prefix.is_empty() {
-                    format!("{}\n\t", prefix)
-                } else {
-                    "\n\t".to_string()
+                // No blank line after the opening bracket.
+                let leading = match leading.trim_start() {
+                    rest if i == 0 && !rest.is_empty() => format!("\n{}", rest),
+                    _ => leading.to_string(),
                 };
 
-                if !prefix.starts_with('\n') {
-                    prefix = format!(" {prefix}");
-                }
-
-                let mut suffix = value
-                    .decor()
-                    .suffix()
-                    .map(|s| s.as_some_str())
-                    .unwrap_or("")
-                    .trim_matches(&[' ', '\t', '\n'][..])
-                    .to_string();
+                let prefix = format!(
+                    "{}{}\n{}",
+                    trailing_comments(&comments, indent),
+                    reindent(&leading, indent),
+                    indent
+                );
+
+                comments = comment_lines(value.decor().suffix())
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect();
+
+                let mut formatted_value = self.format_nested_value(value, path, false);
+                indent_nested_value(&mut formatted_value, indent);
+                *value = formatted_value.decorated(&prefix, "");
+            }
 
-                // If the suffix is non-empty it is important to add a trailing
-                // new line so that the comma is not part of a comment
-                if !suffix.is_empty() {
-                    suffix.push('\n');
-                }
+            let closing = closing.trim_matches(&[' ', '\t'][..]).trim_end();
+            let trailing = format!(
+                "{}{}\n",
+                trailing_comments(&comments, indent),
+                reindent(closing, indent)
+            );
 
-                let formatted_value = self.format_value(value, false)?;
-                *value = formatted_value.decorated(&prefix, &suffix);
-            }
+            new_array.set_trailing(&trailing);
+            new_array.set_trailing_comma(true);
         }
         // Inline array
         else {
-            new_array.set_trailing("");
-            new_array.set_trailing_comma(false);
-
-            let len = new_array.len();
-            for (i, value) in new_array.iter_mut().enumerate() {
-                *value = self.format_value(value, i + 1 == len)?;
-            }
+            self.layout_inline_array(&mut new_array, path);
         }
 
         new_array.decor_mut().set_prefix(" ");
         new_array
             .decor_mut()
-            .set_suffix(if last { " " } else { "" });
+            .set_suffix(value_suffix(array.decor(), last));
 
-        Ok(new_array)
+        new_array
+    }
+
+    /// Format the values of an array on a single line.
+    fn layout_inline_array(&self, array: &mut Array, path: &[String]) {
+        array.set_trailing("");
+        array.set_trailing_comma(false);
+
+        let len = array.len();
+        for (i, value) in array.iter_mut().enumerate() {
+            *value = self.format_nested_value(value, path, i + 1 == len);
+        }
     }
 }
 
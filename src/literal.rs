@@ -0,0 +1,142 @@
+use toml_edit::Value;
+
+/// Normalize the representation of numbers and datetimes:
+/// - no redundant `+` sign,
+/// - digits of decimal numbers written with underscores are grouped by three
+///   (starting from the decimal point in fractions),
+/// - lowercase hexadecimal digits and exponent,
+/// - `T` separator between date and time, and uppercase `Z`.
+///
+/// Returns `None` if the representation is unchanged. The parsed value is
+/// never modified.
+pub fn normalize_literal(value: &Value) -> Option<Value> {
+    let repr = value.clone().decorated("", "").to_string();
+
+    let new_repr = match value {
+        Value::Integer(_) => normalize_integer(&repr),
+        Value::Float(_) => normalize_float(&repr),
+        Value::Datetime(_) => normalize_datetime(&repr),
+        _ => return None,
+    };
+
+    if new_repr == repr {
+        return None;
+    }
+
+    // Ensure the value is unchanged.
+    let new_value = new_repr.parse::<Value>().ok()?;
+    same_value(value, &new_value).then_some(new_value)
+}
+
+fn normalize_integer(repr: &str) -> String {
+    let repr = repr.strip_prefix('+').unwrap_or(repr);
+
+    if let Some(digits) = repr.strip_prefix("0x") {
+        return format!("0x{}", digits.to_lowercase());
+    }
+
+    if repr.starts_with("0o") || repr.starts_with("0b") {
+        return repr.to_string();
+    }
+
+    let (sign, digits) = match repr.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", repr),
+    };
+
+    format!("{}{}", sign, group_digits(digits))
+}
+
+fn normalize_float(repr: &str) -> String {
+    let repr = repr.strip_prefix('+').unwrap_or(repr);
+
+    // `inf` and `nan`
+    if repr
+        .trim_start_matches('-')
+        .chars()
+        .all(char::is_alphabetic)
+    {
+        return repr.to_string();
+    }
+
+    let (mantissa, exponent) = match repr.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => {
+            let exponent = exponent.strip_prefix('+').unwrap_or(exponent);
+            let exponent = match exponent.strip_prefix('-') {
+                Some(digits) => format!("-{}", group_digits(digits)),
+                None => group_digits(exponent),
+            };
+            (mantissa, Some(exponent))
+        }
+        None => (repr, None),
+    };
+
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+
+    let mantissa = match mantissa.split_once('.') {
+        Some((integer, fraction)) => format!(
+            "{}.{}",
+            group_digits(integer),
+            group_fraction_digits(fraction)
+        ),
+        None => group_digits(mantissa),
+    };
+
+    match exponent {
+        Some(exponent) => format!("{}{}e{}", sign, mantissa, exponent),
+        None => format!("{}{}", sign, mantissa),
+    }
+}
+
+fn normalize_datetime(repr: &str) -> String {
+    let mut chars: Vec<char> = repr.chars().collect();
+
+    // Separator between the date and the time (`YYYY-MM-DD`).
+    if chars.len() > 10 && chars[4] == '-' && matches!(chars[10], ' ' | 't') {
+        chars[10] = 'T';
+    }
+
+    if chars.last() == Some(&'z') {
+        chars.pop();
+        chars.push('Z');
+    }
+
+    chars.into_iter().collect()
+}
+
+/// Group decimal digits by three if they are already separated by underscores.
+fn group_digits(digits: &str) -> String {
+    if !digits.contains('_') {
+        return digits.to_string();
+    }
+
+    let digits: Vec<char> = digits.chars().filter(|&c| c != '_').collect();
+    let mut grouped = String::new();
+
+    for (i, c) in digits.iter().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push('_');
+        }
+        grouped.push(*c);
+    }
+
+    grouped
+}
+
+/// Group digits following the decimal point by three, starting from it.
+fn group_fraction_digits(digits: &str) -> String {
+    let reversed: String = digits.chars().rev().collect();
+    group_digits(&reversed).chars().rev().collect()
+}
+
+fn same_value(x: &Value, y: &Value) -> bool {
+    match (x, y) {
+        (Value::Integer(x), Value::Integer(y)) => x.value() == y.value(),
+        (Value::Float(x), Value::Float(y)) => x.value().to_bits() == y.value().to_bits(),
+        (Value::Datetime(x), Value::Datetime(y)) => x.value() == y.value(),
+        _ => false,
+    }
+}
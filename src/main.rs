@@ -4,20 +4,42 @@ use {
     toml_maid::{Config, Opt, Res},
 };
 
-fn main() -> Res {
+fn main() {
     let opt = Opt::from_args();
 
-    let config = Config::read_from_file().unwrap_or_else(|| {
-        if !opt.silent {
-            println!(
-                "{}",
-                "No 'toml-maid.toml' in this directory and its parents, using default config.\n"
-                    .yellow()
-            );
+    if opt.no_color {
+        colored::control::set_override(false);
+    }
+
+    match try_main(opt) {
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            eprintln!("{}", e.to_string().red());
+            std::process::exit(1);
         }
+    }
+}
 
-        Config::default()
-    });
+/// Run the formatter and return the process exit code.
+fn try_main(opt: Opt) -> Res<i32> {
+    let config = match &opt.config {
+        Some(path) => Config::load(path)?,
+        None => match Config::read_from_dir(opt.config_dir()?)? {
+            Some(config) => config,
+            None => {
+                // Nothing but the formatted document should be printed in stdout.
+                if !opt.silent && !opt.reads_stdin() {
+                    println!("{}", NO_CONFIG_MESSAGE.yellow());
+                }
 
-    toml_maid::run(opt, config)
+                Config::default()
+            }
+        },
+    };
+
+    let report = toml_maid::run(opt, config)?;
+    Ok(report.exit_code())
 }
+
+const NO_CONFIG_MESSAGE: &str =
+    "No 'toml-maid.toml' in this directory and its parents, using default config.\n";
@@ -0,0 +1,189 @@
+use {
+    serde::{Deserialize, Serialize},
+    std::{fmt, path::Path},
+};
+
+/// Pattern matching the dotted path of a table or value, such as `package`,
+/// `dependencies.*` or `target.*.dependencies`.
+///
+/// Each segment is a glob pattern matching a single key (`*`, `?` and `[...]`
+/// are supported), while a `**` segment matches any number of keys. Segments
+/// can be quoted (`"a.b".c`) to include dots.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyPattern {
+    raw: String,
+    segments: Vec<Segment>,
+}
+
+/// Glob pattern matching a file path relative to the config file, such as
+/// `**/Cargo.toml`. `*` doesn't match path separators.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct FilePattern(glob::Pattern);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// `**`, matches any number of keys.
+    Any,
+    /// Matches a single key.
+    Key(glob::Pattern),
+}
+
+impl KeyPattern {
+    /// Parse a dotted path pattern.
+    pub fn new(raw: &str) -> Result<Self, String> {
+        let segments = split_segments(raw)?
+            .into_iter()
+            .map(|segment| {
+                if segment == "**" {
+                    Ok(Segment::Any)
+                } else {
+                    glob::Pattern::new(&segment)
+                        .map(Segment::Key)
+                        .map_err(|e| format!("invalid pattern \"{}\": {}", raw, e))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            raw: raw.to_string(),
+            segments,
+        })
+    }
+
+    /// Does this pattern match the provided path of keys?
+    pub fn matches<S: AsRef<str>>(&self, path: &[S]) -> bool {
+        fn matches<S: AsRef<str>>(segments: &[Segment], path: &[S]) -> bool {
+            match (segments.first(), path.first()) {
+                (None, None) => true,
+                (Some(Segment::Any), _) => {
+                    matches(&segments[1..], path)
+                        || (!path.is_empty() && matches(segments, &path[1..]))
+                }
+                (Some(Segment::Key(pattern)), Some(key)) => {
+                    pattern.matches(key.as_ref()) && matches(&segments[1..], &path[1..])
+                }
+                _ => false,
+            }
+        }
+
+        matches(&self.segments, path)
+    }
+
+    /// Does this pattern match the provided path of keys, or paths starting
+    /// with it?
+    pub fn matches_prefix<S: AsRef<str>>(&self, path: &[S]) -> bool {
+        fn matches<S: AsRef<str>>(segments: &[Segment], path: &[S]) -> bool {
+            match (segments.first(), path.first()) {
+                (_, None) => true,
+                (Some(Segment::Any), _) => {
+                    matches(&segments[1..], path) || matches(segments, &path[1..])
+                }
+                (Some(Segment::Key(pattern)), Some(key)) => {
+                    pattern.matches(key.as_ref()) && matches(&segments[1..], &path[1..])
+                }
+                (None, Some(_)) => false,
+            }
+        }
+
+        matches(&self.segments, path)
+    }
+
+    /// Pattern as written in the config.
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+}
+
+impl fmt::Display for KeyPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl TryFrom<String> for KeyPattern {
+    type Error = String;
+
+    fn try_from(x: String) -> Result<Self, Self::Error> {
+        Self::new(&x)
+    }
+}
+
+impl From<KeyPattern> for String {
+    fn from(x: KeyPattern) -> Self {
+        x.raw
+    }
+}
+
+impl FilePattern {
+    /// Parse a glob pattern.
+    pub fn new(raw: &str) -> Result<Self, String> {
+        glob::Pattern::new(raw)
+            .map(Self)
+            .map_err(|e| format!("invalid pattern \"{}\": {}", raw, e))
+    }
+
+    /// Does this pattern match the provided path?
+    pub fn matches(&self, path: &Path) -> bool {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+
+        self.0.matches_path_with(path, options)
+    }
+
+    /// Pattern as written in the config.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl fmt::Display for FilePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<String> for FilePattern {
+    type Error = String;
+
+    fn try_from(x: String) -> Result<Self, Self::Error> {
+        Self::new(&x)
+    }
+}
+
+impl From<FilePattern> for String {
+    fn from(x: FilePattern) -> Self {
+        x.0.as_str().to_string()
+    }
+}
+
+/// Split a dotted path into its segments, removing quotes around quoted segments.
+fn split_segments(raw: &str) -> Result<Vec<String>, String> {
+    let mut segments = vec![];
+    let mut segment = String::new();
+    let mut quote = None;
+
+    for c in raw.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '.') => segments.push(std::mem::take(&mut segment)),
+            (_, c) => segment.push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err(format!("unclosed quote in pattern \"{}\"", raw));
+    }
+
+    segments.push(segment);
+
+    if segments.iter().any(|s| s.is_empty()) {
+        return Err(format!("empty key in pattern \"{}\"", raw));
+    }
+
+    Ok(segments)
+}
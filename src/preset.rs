@@ -0,0 +1,181 @@
+use {
+    crate::{Config, KeyPattern, TableRule},
+    serde::{Deserialize, Serialize},
+};
+
+/// Keys of `[package]` (and `[workspace.package]`) in the order of the Cargo
+/// manifest documentation.
+const PACKAGE_KEYS: &[&str] = &[
+    "name",
+    "version",
+    "authors",
+    "edition",
+    "rust-version",
+    "description",
+    "documentation",
+    "readme",
+    "homepage",
+    "repository",
+    "license",
+    "license-file",
+    "keywords",
+    "categories",
+    "workspace",
+    "build",
+    "links",
+    "exclude",
+    "include",
+    "publish",
+    "metadata",
+    "default-run",
+    "autolib",
+    "autobins",
+    "autoexamples",
+    "autotests",
+    "autobenches",
+    "resolver",
+];
+
+const WORKSPACE_KEYS: &[&str] = &[
+    "resolver",
+    "members",
+    "default-members",
+    "exclude",
+    "package",
+    "dependencies",
+    "lints",
+    "metadata",
+];
+
+/// Keys of `[lib]`, `[[bin]]`, `[[example]]`, `[[test]]` and `[[bench]]`.
+const TARGET_KEYS: &[&str] = &[
+    "name",
+    "path",
+    "test",
+    "doctest",
+    "bench",
+    "doc",
+    "proc-macro",
+    "harness",
+    "edition",
+    "crate-type",
+    "required-features",
+];
+
+const DEPENDENCY_KEYS: &[&str] = &[
+    "workspace",
+    "version",
+    "path",
+    "git",
+    "branch",
+    "tag",
+    "rev",
+    "registry",
+    "package",
+    "default-features",
+    "features",
+    "optional",
+];
+
+const PROFILE_KEYS: &[&str] = &[
+    "inherits",
+    "opt-level",
+    "debug",
+    "split-debuginfo",
+    "strip",
+    "debug-assertions",
+    "overflow-checks",
+    "lto",
+    "panic",
+    "incremental",
+    "codegen-units",
+    "rpath",
+];
+
+/// Order of the table headers in the Cargo manifest documentation.
+const TABLE_ORDER: &[&str] = &[
+    "package",
+    "lib",
+    "bin",
+    "example",
+    "test",
+    "bench",
+    "dependencies",
+    "dev-dependencies",
+    "build-dependencies",
+    "target",
+    "badges",
+    "features",
+    "lints",
+    "patch",
+    "replace",
+    "profile",
+    "workspace",
+];
+
+/// Built-in settings for some kinds of files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    /// Canonical ordering of `Cargo.toml` manifests.
+    Cargo,
+}
+
+impl Preset {
+    /// Extend `config` with the settings of this preset. Rules of `tables`
+    /// are checked before the ones of the preset, such that they override the
+    /// settings they provide (`keys` or `dotted_keys`) and keep the other ones
+    /// of the preset. `table_order` replaces the one of the preset if not empty.
+    pub fn extend(self, config: &mut Config) {
+        let (tables, table_order) = match self {
+            Self::Cargo => (cargo_tables(), TABLE_ORDER),
+        };
+
+        config.tables.extend(tables);
+
+        if config.table_order.is_empty() {
+            config.table_order = table_order.iter().map(|raw| pattern(raw)).collect();
+        }
+    }
+}
+
+fn cargo_tables() -> Vec<TableRule<Vec<String>>> {
+    let mut rules = vec![
+        rule("package", PACKAGE_KEYS),
+        rule("workspace", WORKSPACE_KEYS),
+        rule("workspace.package", PACKAGE_KEYS),
+        rule("lib", TARGET_KEYS),
+        rule("bin", TARGET_KEYS),
+        rule("example", TARGET_KEYS),
+        rule("test", TARGET_KEYS),
+        rule("bench", TARGET_KEYS),
+        rule("profile.*", PROFILE_KEYS),
+    ];
+
+    for dependencies in [
+        "dependencies",
+        "dev-dependencies",
+        "build-dependencies",
+        "workspace.dependencies",
+        "target.*.dependencies",
+        "target.*.dev-dependencies",
+        "target.*.build-dependencies",
+        "patch.*",
+    ] {
+        rules.push(rule(&format!("{}.*", dependencies), DEPENDENCY_KEYS));
+    }
+
+    rules
+}
+
+fn rule(path: &str, keys: &[&str]) -> TableRule<Vec<String>> {
+    TableRule {
+        path: pattern(path),
+        keys: Some(keys.iter().map(|key| key.to_string()).collect()),
+        dotted_keys: None,
+    }
+}
+
+fn pattern(raw: &str) -> KeyPattern {
+    KeyPattern::new(raw).expect("preset patterns are valid")
+}
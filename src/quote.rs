@@ -0,0 +1,114 @@
+use {
+    serde::{Deserialize, Serialize},
+    toml_edit::Value,
+};
+
+/// Which quotes are used for strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QuoteStyle {
+    /// Basic strings (`"..."`), literal strings (`'...'`) which would need
+    /// escapes being kept.
+    #[default]
+    Double,
+    /// Literal strings (`'...'`) whenever the content allows it, unicode
+    /// escapes being kept.
+    Single,
+    /// Strings are kept as written.
+    Preserve,
+}
+
+impl QuoteStyle {
+    /// Rewrite a string value with the quotes of this style, if it changes its
+    /// representation. Multi-line strings stay multi-line, and the content of
+    /// the string is never modified.
+    pub fn requote(self, value: &Value) -> Option<Value> {
+        let content = match value {
+            Value::String(s) => s.value(),
+            _ => return None,
+        };
+
+        let repr = value.clone().decorated("", "").to_string();
+        let literal = repr.starts_with('\'');
+        let layout = Layout {
+            multiline: repr.starts_with("'''") || repr.starts_with("\"\"\""),
+            // Keep the newline following the opening quotes, which is trimmed.
+            newline: repr.get(3..).is_some_and(|s| s.starts_with(['\n', '\r'])),
+        };
+
+        // Line continuations would be lost in literal strings.
+        if !literal && escapes(&repr).any(|c| matches!(c, ' ' | '\t' | '\r' | '\n')) {
+            return None;
+        }
+
+        let new_repr = match (self, literal) {
+            (Self::Preserve, _) => None,
+            (Self::Double, true) => basic_repr(content, layout),
+            (Self::Double, false) => None,
+            // Unicode escapes are kept as written.
+            (Self::Single, false) if escapes(&repr).any(|c| matches!(c, 'u' | 'U')) => None,
+            (Self::Single, false) => literal_repr(content, layout),
+            (Self::Single, true) => None,
+        }?;
+
+        // Ensure the content is unchanged.
+        let new_value = new_repr.parse::<Value>().ok()?;
+        (new_value.as_str() == Some(content)).then_some(new_value)
+    }
+}
+
+/// Layout of a string representation.
+#[derive(Clone, Copy)]
+struct Layout {
+    multiline: bool,
+    /// Newline after the opening quotes of a multi-line string.
+    newline: bool,
+}
+
+/// Basic string representation, if the content doesn't need escapes.
+fn basic_repr(content: &str, layout: Layout) -> Option<String> {
+    let valid = |c: char| c != '"' && c != '\\' && allowed(c, layout);
+    let quotes = if layout.multiline { "\"\"\"" } else { "\"" };
+
+    content
+        .chars()
+        .all(valid)
+        .then(|| quote(content, quotes, layout))
+}
+
+/// Literal string representation, if the content allows it.
+fn literal_repr(content: &str, layout: Layout) -> Option<String> {
+    let valid = |c: char| c != '\'' && allowed(c, layout);
+    let quotes = if layout.multiline { "'''" } else { "'" };
+
+    content
+        .chars()
+        .all(valid)
+        .then(|| quote(content, quotes, layout))
+}
+
+/// Can the character be written as is in a string? Escaped control characters
+/// (such as tabs) are kept escaped, except newlines in multi-line strings.
+fn allowed(c: char, layout: Layout) -> bool {
+    !c.is_control() || (layout.multiline && c == '\n')
+}
+
+fn quote(content: &str, quotes: &str, layout: Layout) -> String {
+    // A newline following the opening quotes of a multi-line string is trimmed.
+    if layout.multiline && (layout.newline || content.starts_with('\n')) {
+        format!("{}\n{}{}", quotes, content, quotes)
+    } else {
+        format!("{}{}{}", quotes, content, quotes)
+    }
+}
+
+/// Characters following a backslash in a basic string representation.
+fn escapes(repr: &str) -> impl Iterator<Item = char> + '_ {
+    let mut chars = repr.chars();
+
+    std::iter::from_fn(move || loop {
+        if chars.next()? == '\\' {
+            return chars.next();
+        }
+    })
+}
@@ -0,0 +1,238 @@
+use {
+    serde::{Deserialize, Serialize},
+    std::{cmp::Ordering, iter::Peekable, str::Chars},
+    toml_edit::{Datetime, InlineTable, Offset, Value},
+};
+
+/// How keys and strings are compared when sorting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortMode {
+    /// Byte-wise comparison, uppercase letters being sorted before lowercase ones.
+    #[default]
+    Lexicographic,
+    /// Comparison ignoring case.
+    CaseInsensitive,
+    /// Comparison ignoring case, where sequences of digits are compared
+    /// numerically (`pallet-2` before `pallet-10`).
+    Natural,
+}
+
+impl SortMode {
+    /// Compare two strings according to this mode.
+    /// Strings considered equal by the mode are then compared byte-wise so that
+    /// the ordering is deterministic.
+    pub fn compare(self, x: &str, y: &str) -> Ordering {
+        let ord = match self {
+            Self::Lexicographic => Ordering::Equal,
+            Self::CaseInsensitive => x
+                .chars()
+                .flat_map(char::to_lowercase)
+                .cmp(y.chars().flat_map(char::to_lowercase)),
+            Self::Natural => compare_natural(x, y),
+        };
+
+        ord.then_with(|| x.cmp(y))
+    }
+}
+
+/// Compare strings by chunks, digits chunks being compared numerically and
+/// other chunks ignoring case.
+fn compare_natural(x: &str, y: &str) -> Ordering {
+    let mut x = x.chars().peekable();
+    let mut y = y.chars().peekable();
+
+    loop {
+        match (x.peek().copied(), y.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) if a.is_ascii_digit() && b.is_ascii_digit() => {
+                let a = take_digits(&mut x);
+                let b = take_digits(&mut y);
+
+                // Compare numerically without parsing to support any length.
+                let ord = a.len().cmp(&b.len()).then_with(|| a.cmp(&b));
+
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(a), Some(b)) => {
+                let ord = a.to_lowercase().cmp(b.to_lowercase());
+
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+
+                x.next();
+                y.next();
+            }
+        }
+    }
+}
+
+/// Take a sequence of digits, without leading zeros.
+fn take_digits(chars: &mut Peekable<Chars>) -> String {
+    let mut digits = String::new();
+
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        if !(digits.is_empty() && c == '0') {
+            digits.push(c);
+        }
+    }
+
+    digits
+}
+
+/// Total ordering of values used to sort arrays.
+///
+/// Values of different kinds are ordered by kind: strings, then numbers, then
+/// booleans, then datetimes, then arrays, and finally inline tables.
+/// Inside each kind:
+/// - strings are compared by their content (not their representation) using
+///   the provided `SortMode`,
+/// - integers and floats are compared numerically together (NaN last),
+/// - `false` comes before `true`,
+/// - datetimes are compared chronologically (offset datetimes are converted to
+///   UTC, local ones are compared as is, and times without date come last),
+/// - arrays are compared element by element,
+/// - inline tables are compared by the value of `inline_table_key`, tables
+///   without that key coming last.
+///
+/// Values considered equal keep their original order as the sort is stable.
+pub fn compare_values(
+    x: &Value,
+    y: &Value,
+    mode: SortMode,
+    inline_table_key: Option<&str>,
+) -> Ordering {
+    match (x, y) {
+        (Value::String(x), Value::String(y)) => mode.compare(x.value(), y.value()),
+        (Value::Integer(x), Value::Integer(y)) => x.value().cmp(y.value()),
+        (Value::Integer(x), Value::Float(y)) => compare_integer_float(*x.value(), *y.value()),
+        (Value::Float(x), Value::Integer(y)) => {
+            compare_integer_float(*y.value(), *x.value()).reverse()
+        }
+        (Value::Float(x), Value::Float(y)) => compare_floats(*x.value(), *y.value()),
+        (Value::Boolean(x), Value::Boolean(y)) => x.value().cmp(y.value()),
+        (Value::Datetime(x), Value::Datetime(y)) => {
+            datetime_key(x.value()).cmp(&datetime_key(y.value()))
+        }
+        (Value::Array(x), Value::Array(y)) => {
+            for (x, y) in x.iter().zip(y.iter()) {
+                match compare_values(x, y, mode, inline_table_key) {
+                    Ordering::Equal => continue,
+                    ord => return ord,
+                }
+            }
+
+            x.len().cmp(&y.len())
+        }
+        (Value::InlineTable(x), Value::InlineTable(y)) => {
+            compare_inline_tables(x, y, mode, inline_table_key)
+        }
+        (x, y) => kind_rank(x).cmp(&kind_rank(y)),
+    }
+}
+
+fn compare_inline_tables(
+    x: &InlineTable,
+    y: &InlineTable,
+    mode: SortMode,
+    key: Option<&str>,
+) -> Ordering {
+    let key = match key {
+        Some(key) => key,
+        None => return Ordering::Equal,
+    };
+
+    match (x.get(key), y.get(key)) {
+        (Some(x), Some(y)) => compare_values(x, y, mode, Some(key)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Rank of each kind of value, used to compare values of different kinds.
+fn kind_rank(value: &Value) -> u8 {
+    match value {
+        Value::String(_) => 0,
+        Value::Integer(_) | Value::Float(_) => 1,
+        Value::Boolean(_) => 2,
+        Value::Datetime(_) => 3,
+        Value::Array(_) => 4,
+        Value::InlineTable(_) => 5,
+    }
+}
+
+/// Compare numerically, with NaN values last.
+fn compare_floats(x: f64, y: f64) -> Ordering {
+    match (x.is_nan(), y.is_nan()) {
+        (false, false) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+        (false, true) => Ordering::Less,
+        (true, false) => Ordering::Greater,
+        (true, true) => Ordering::Equal,
+    }
+}
+
+/// Compare an integer and a float exactly, as converting large integers to
+/// floats loses precision (NaN last).
+fn compare_integer_float(x: i64, y: f64) -> Ordering {
+    // 2^63, the first float above `i64::MAX`.
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+
+    if y.is_nan() || y >= LIMIT {
+        return Ordering::Less;
+    }
+    if y < -LIMIT {
+        return Ordering::Greater;
+    }
+
+    // The integer part of `y` fits in an `i64`.
+    let integer = y.trunc();
+    x.cmp(&(integer as i64))
+        .then_with(|| compare_floats(0.0, y - integer))
+}
+
+/// Key used to compare datetimes chronologically:
+/// whether it lacks a date, seconds since the Unix epoch (UTC for offset
+/// datetimes) and nanoseconds.
+fn datetime_key(datetime: &Datetime) -> (bool, i64, u32) {
+    let days = datetime
+        .date
+        .map(|date| days_from_civil(date.year.into(), date.month.into(), date.day.into()))
+        .unwrap_or(0);
+
+    let (seconds, nanoseconds) = datetime
+        .time
+        .map(|time| {
+            let seconds =
+                i64::from(time.hour) * 3600 + i64::from(time.minute) * 60 + i64::from(time.second);
+            (seconds, time.nanosecond)
+        })
+        .unwrap_or((0, 0));
+
+    let offset = match datetime.offset {
+        Some(Offset::Custom { minutes }) => i64::from(minutes) * 60,
+        Some(Offset::Z) | None => 0,
+    };
+
+    (
+        datetime.date.is_none(),
+        days * 86_400 + seconds - offset,
+        nanoseconds,
+    )
+}
+
+/// Number of days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
@@ -0,0 +1,17 @@
+use std::path::{Path, PathBuf};
+
+/// Empty temporary directory for the test `name` of the provided group.
+pub fn test_dir(group: &str, name: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join(format!("toml-maid-{}", group))
+        .join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("to create test dir");
+    dir
+}
+
+/// Write a file, creating its parent directories.
+pub fn write(path: &Path, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).expect("to create parent dir");
+    std::fs::write(path, content).expect("to write test file");
+}
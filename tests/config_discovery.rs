@@ -0,0 +1,197 @@
+use std::path::PathBuf;
+
+use toml_maid::{Config, Error, Opt, Outcome};
+
+use common::{test_dir, write};
+
+mod common;
+
+#[test]
+fn nearest_config_is_used_for_each_file() {
+    let dir = test_dir("config", "nearest");
+    write(&dir.join("toml-maid.toml"), "keys = [\"b\"]\n");
+    write(
+        &dir.join("sub/project/toml-maid.toml"),
+        "keys = [\"c\"]\nexcludes = [\"ignored/**\"]\n",
+    );
+    write(&dir.join("root.toml"), "a = 1\nb = 2\nc = 3\n");
+    write(&dir.join("sub/project/crate.toml"), "a = 1\nb = 2\nc = 3\n");
+    write(&dir.join("sub/project/ignored/crate.toml"), "c = 3\n");
+
+    let opt = Opt {
+        folder: vec![dir.clone()],
+        check: true,
+        silent: true,
+        per_file_config: true,
+        ..Default::default()
+    };
+
+    let report = toml_maid::run(opt, Config::default()).expect("to run without errors");
+
+    let mut outcomes: Vec<_> = report
+        .files
+        .iter()
+        .map(|file| {
+            let path = file.path.strip_prefix(&dir).unwrap().to_path_buf();
+            (path, matches!(file.outcome, Outcome::WouldReformat))
+        })
+        .collect();
+    outcomes.sort();
+
+    assert_eq!(
+        outcomes,
+        vec![
+            (PathBuf::from("root.toml"), true),
+            (PathBuf::from("sub/project/crate.toml"), true),
+        ]
+    );
+
+    // Only formatted with the nearest config.
+    let opt = Opt {
+        files: vec![dir.join("sub/project/crate.toml")],
+        silent: true,
+        per_file_config: true,
+        ..Default::default()
+    };
+
+    toml_maid::run(opt, Config::default()).expect("to run without errors");
+
+    let output = std::fs::read_to_string(dir.join("sub/project/crate.toml")).unwrap();
+    assert_eq!(output, "c = 3\na = 1\nb = 2\n");
+}
+
+#[test]
+fn explicit_config_is_loaded() {
+    let dir = test_dir("config", "explicit");
+    write(&dir.join("custom.toml"), "keys = [\"b\"]\n");
+
+    let config = Config::load(dir.join("custom.toml")).expect("to load config");
+    assert_eq!(config.keys, vec!["b".to_string()]);
+
+    assert!(Config::load(dir.join("missing.toml")).is_err());
+}
+
+#[test]
+fn invalid_config_is_reported() {
+    let dir = test_dir("config", "invalid");
+
+    assert!(matches!(Config::read_from_dir(&dir), Ok(None)));
+
+    write(&dir.join("toml-maid.toml"), "sort_arrays = \"yes\"\n");
+    assert!(matches!(
+        Config::read_from_dir(&dir),
+        Err(Error::Config {
+            line: Some(1),
+            column: Some(15),
+            ..
+        })
+    ));
+
+    write(
+        &dir.join("toml-maid.toml"),
+        "keys = [\"name\"]\nsort-arrays = true\n",
+    );
+    match Config::read_from_dir(&dir) {
+        Err(Error::Config {
+            path,
+            line,
+            message,
+            ..
+        }) => {
+            assert_eq!(path, Some(dir.join("toml-maid.toml")));
+            assert_eq!(line, Some(2));
+            assert!(message.contains("unknown field `sort-arrays`"));
+        }
+        res => panic!("unexpected result: {:?}", res),
+    }
+
+    // The key is searched in the table reporting it.
+    write(
+        &dir.join("toml-maid.toml"),
+        "[[tables]]\npath = \"a\"\n\n[[overrides]]\nfiles = [\"x\"]\npath = \"b\"\n",
+    );
+    assert!(matches!(
+        Config::read_from_dir(&dir),
+        Err(Error::Config { line: Some(6), .. })
+    ));
+}
+
+#[test]
+fn invalid_override_pattern_is_reported() {
+    let dir = test_dir("config", "invalid_override");
+    write(
+        &dir.join("toml-maid.toml"),
+        "[[overrides]]\nfiles = [\"[\"]\nsort_arrays = true\n",
+    );
+
+    match Config::read_from_dir(&dir) {
+        Err(Error::Config { path, message, .. }) => {
+            assert_eq!(path, Some(dir.join("toml-maid.toml")));
+            assert!(message.contains("invalid pattern \"[\""));
+        }
+        res => panic!("unexpected result: {:?}", res),
+    }
+}
+
+#[test]
+fn overrides_apply_to_matching_files() {
+    let dir = test_dir("config", "overrides");
+    write(
+        &dir.join("toml-maid.toml"),
+        r#"
+sort_arrays = false
+
+[[overrides]]
+files = ["**/Cargo.toml"]
+sort_arrays = true
+
+[[overrides]]
+files = ["config/*.toml"]
+keys = ["b"]
+"#,
+    );
+    write(&dir.join("Cargo.toml"), "a = [ \"b\", \"a\" ]\n");
+    write(&dir.join("sub/Cargo.toml"), "a = [ \"b\", \"a\" ]\n");
+    write(
+        &dir.join("config/runtime.toml"),
+        "a = [ \"b\", \"a\" ]\nb = 1\n",
+    );
+    write(&dir.join("config/nested/runtime.toml"), "a = 1\nb = 1\n");
+
+    let opt = Opt {
+        folder: vec![dir.clone()],
+        silent: true,
+        per_file_config: true,
+        ..Default::default()
+    };
+
+    toml_maid::run(opt, Config::default()).expect("to run without errors");
+
+    let read = |path: &str| std::fs::read_to_string(dir.join(path)).unwrap();
+    assert_eq!(read("Cargo.toml"), "a = [ \"a\", \"b\" ]\n");
+    assert_eq!(read("sub/Cargo.toml"), "a = [ \"a\", \"b\" ]\n");
+    assert_eq!(read("config/runtime.toml"), "b = 1\na = [ \"b\", \"a\" ]\n");
+    assert_eq!(read("config/nested/runtime.toml"), "a = 1\nb = 1\n");
+}
+
+#[test]
+fn excludes_are_relative_to_scanned_folder() {
+    let dir = test_dir("config", "excludes");
+    write(&dir.join("toml-maid.toml"), "excludes = [\"ignored/**\"]\n");
+    write(&dir.join("sub/crate.toml"), "b = 1\na = 1\n");
+    write(&dir.join("sub/ignored/crate.toml"), "b = 1\na = 1\n");
+
+    let opt = Opt {
+        folder: vec![dir.join("sub")],
+        config: Some(dir.join("toml-maid.toml")),
+        check: true,
+        silent: true,
+        ..Default::default()
+    };
+    let config = Config::load(dir.join("toml-maid.toml")).expect("to load config");
+
+    let report = toml_maid::run(opt, config).expect("to run without errors");
+    let files: Vec<_> = report.files.iter().map(|file| &file.path).collect();
+
+    assert_eq!(files, vec![&dir.join("sub/crate.toml")]);
+}
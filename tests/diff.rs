@@ -0,0 +1,34 @@
+use toml_maid::unified_diff;
+
+#[test]
+fn unified_diff_shows_changes_with_context() {
+    colored::control::set_override(false);
+
+    let common = "d = 4\ne = 5\nf = 6\ng = 7\nh = 8\ni = 9\nj = 10\n";
+    let old = format!("a = 1\nc = 3\nb = 2\n{}k = 'x'", common);
+    let new = format!("a = 1\nb = 2\nc = 3\n{}k = \"x\"\n", common);
+
+    let diff = unified_diff(&old, &new, "file.toml", "file.toml");
+
+    assert_eq!(
+        diff,
+        "--- file.toml
++++ file.toml
+@@ -1,6 +1,6 @@
+ a = 1
+-c = 3
+ b = 2
++c = 3
+ d = 4
+ e = 5
+ f = 6
+@@ -8,4 +8,4 @@
+ h = 8
+ i = 9
+ j = 10
+-k = 'x'
+\\ No newline at end of file
++k = \"x\"
+"
+    );
+}
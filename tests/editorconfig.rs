@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use toml_maid::{Config, Opt};
+
+use common::{test_dir, write};
+
+mod common;
+
+fn format(file: &Path, config: Config) -> String {
+    let opt = Opt {
+        files: vec![file.to_path_buf()],
+        silent: true,
+        ..Default::default()
+    };
+
+    toml_maid::run(opt, config)
+        .and_then(|report| report.into_result())
+        .expect("to run without errors");
+
+    std::fs::read_to_string(file).expect("to read formatted file")
+}
+
+const EDITORCONFIG: &str = "root = true
+
+[*]
+indent_style = tab
+
+[*.{toml,json}]
+indent_style = space
+indent_size = 2
+
+[tabs/**.toml]
+indent_style = tab
+";
+
+const INPUT: &str = "a = [\n\"x\",\n\"y\",\n]\n";
+
+#[test]
+fn indent_is_read_from_editorconfig() {
+    let dir = test_dir("editorconfig", "detect");
+    write(&dir.join(".editorconfig"), EDITORCONFIG);
+    write(&dir.join("sub/spaces.toml"), INPUT);
+    write(&dir.join("tabs/nested/tabs.toml"), INPUT);
+
+    assert_eq!(
+        format(&dir.join("sub/spaces.toml"), Config::default()),
+        "a = [\n  \"x\",\n  \"y\",\n]\n"
+    );
+    assert_eq!(
+        format(&dir.join("tabs/nested/tabs.toml"), Config::default()),
+        "a = [\n\t\"x\",\n\t\"y\",\n]\n"
+    );
+}
+
+#[test]
+fn config_indent_takes_precedence_over_editorconfig() {
+    let dir = test_dir("editorconfig", "precedence");
+    write(&dir.join(".editorconfig"), EDITORCONFIG);
+    write(&dir.join("file.toml"), INPUT);
+
+    let config: Config = toml::from_str("indent = 4").unwrap();
+
+    assert_eq!(
+        format(&dir.join("file.toml"), config),
+        "a = [\n    \"x\",\n    \"y\",\n]\n"
+    );
+}
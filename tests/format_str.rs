@@ -0,0 +1,28 @@
+use toml_maid::{Config, FormatError, ProcessedConfig};
+
+#[test]
+fn format_str_matches_output_consistency() {
+    let config: ProcessedConfig = Config::default().into();
+    let input = std::fs::read_to_string("tests/output_consistency/exemple.toml")
+        .expect("to read input file");
+    let expected = std::fs::read_to_string("tests/output_consistency/exemple.toml.out")
+        .expect("to read expected file");
+
+    let output = config.format_str(&input).expect("to format without errors");
+    assert_eq!(output, expected);
+
+    let output = config
+        .format_str(&output)
+        .expect("to format without errors");
+    assert_eq!(output, expected, "formatter output is not stable");
+}
+
+#[test]
+fn format_str_reports_parse_errors() {
+    let config: ProcessedConfig = Config::default().into();
+
+    assert!(matches!(
+        config.format_str("[invalid"),
+        Err(FormatError::Parse(_))
+    ));
+}
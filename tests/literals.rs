@@ -0,0 +1,83 @@
+use toml_maid::{Config, ProcessedConfig};
+
+const INPUT: &str = r#"a = +5
+b = 1_000
+c = 10_00_000
+d = -1_0000
+e = 0xDEAD_beef
+f = 0o755
+g = 0b1101
+h = 1e3
+i = 1E3
+j = +1.5E+3
+k = -2_5000.000_1e-2
+l = +inf
+m = -inf
+n = 1979-05-27 07:32:00Z
+o = 1979-05-27t07:32:00.999z
+p = 1979-05-27 07:32:00-07:00
+q = 1979-05-27
+r = 07:32:00
+s = true
+t = 1_2.3_4_5_6_7E1_0
+u = 1.5e-1_0
+"#;
+
+const EXPECTED: &str = r#"a = 5
+b = 1_000
+c = 1_000_000
+d = -10_000
+e = 0xdead_beef
+f = 0o755
+g = 0b1101
+h = 1e3
+i = 1e3
+j = 1.5e3
+k = -25_000.000_1e-2
+l = inf
+m = -inf
+n = 1979-05-27T07:32:00Z
+o = 1979-05-27T07:32:00.999Z
+p = 1979-05-27T07:32:00-07:00
+q = 1979-05-27
+r = 07:32:00
+s = true
+t = 12.345_67e10
+u = 1.5e-10
+"#;
+
+fn config() -> ProcessedConfig {
+    let config: Config = toml::from_str("normalize_literals = true").unwrap();
+    config.into()
+}
+
+#[test]
+fn literals_are_normalized() {
+    let output = config()
+        .format_str(INPUT)
+        .expect("to format without errors");
+    assert_eq!(output, EXPECTED);
+
+    let output = config()
+        .format_str(&output)
+        .expect("to format without errors");
+    assert_eq!(output, EXPECTED, "formatter output is not stable");
+}
+
+#[test]
+fn normalization_keeps_values() {
+    let output = config()
+        .format_str(INPUT)
+        .expect("to format without errors");
+
+    let input: toml::Value = toml::from_str(INPUT).unwrap();
+    let output: toml::Value = toml::from_str(&output).unwrap();
+    assert_eq!(input, output);
+}
+
+#[test]
+fn literals_are_kept_by_default() {
+    let config: ProcessedConfig = Config::default().into();
+    let output = config.format_str(INPUT).expect("to format without errors");
+    assert_eq!(output, INPUT);
+}
@@ -10,8 +10,6 @@ fn ensure_output_consistency() {
     let extension: OsString = "toml".into();
     let files = std::fs::read_dir(&files_path).expect("to read dir content");
 
-    let config = Config::default();
-
     for file in files {
         let file = file.expect("can get file info");
 
@@ -21,6 +19,14 @@ fn ensure_output_consistency() {
             continue;
         }
 
+        // Fixtures can provide their own config in a `.toml.config` file.
+        let config_path = file.path().with_extension("toml.config");
+        let config = if config_path.is_file() {
+            Config::load(&config_path).expect("to load fixture config")
+        } else {
+            Config::default()
+        };
+
         std::fs::copy(file.path(), &test_file).expect("copy to work");
 
         let opt = Opt {
@@ -28,9 +34,12 @@ fn ensure_output_consistency() {
             folder: vec![],
             check: false,
             silent: true,
+            ..Default::default()
         };
 
-        toml_maid::run(opt.clone(), config.clone()).expect("to run without errors");
+        toml_maid::run(opt.clone(), config.clone())
+            .and_then(|report| report.into_result())
+            .expect("to run without errors");
 
         // We now check that the result matches the expectations
         let expected_path = file.path().with_extension("toml.out");
@@ -40,7 +49,9 @@ fn ensure_output_consistency() {
         assert_eq!(output, expected, "formatter output should match expected");
 
         // Rerun formatter to ensure formatting is stable
-        toml_maid::run(opt.clone(), config.clone()).expect("to run without errors");
+        toml_maid::run(opt.clone(), config.clone())
+            .and_then(|report| report.into_result())
+            .expect("to run without errors");
 
         let output = std::fs::read(&test_file).expect("to read test file");
         assert_eq!(output, expected, "formatter output is not stable");
@@ -0,0 +1,66 @@
+use toml_maid::{Config, Error, Opt, Outcome};
+
+use common::{test_dir, write};
+
+mod common;
+
+#[test]
+fn check_lists_every_file() {
+    let dir = test_dir("report", "check");
+    let formatted = dir.join("formatted.toml");
+    let unformatted1 = dir.join("unformatted1.toml");
+    let unformatted2 = dir.join("unformatted2.toml");
+    write(&formatted, "a = 1\nb = 2\n");
+    write(&unformatted1, "b = 2\na = 1\n");
+    write(&unformatted2, "a = 'x'\n");
+
+    let opt = Opt {
+        files: vec![unformatted1.clone(), formatted, unformatted2.clone()],
+        check: true,
+        silent: true,
+        ..Default::default()
+    };
+
+    let report = toml_maid::run(opt, Config::default()).expect("to run without errors");
+
+    assert_eq!(report.files.len(), 3);
+    assert_eq!(
+        report.check_failures().collect::<Vec<_>>(),
+        vec![unformatted1.as_path(), unformatted2.as_path()]
+    );
+    assert_eq!(report.exit_code(), 2);
+    assert!(matches!(
+        report.into_result(),
+        Err(Error::CheckFailed { path }) if path == unformatted1
+    ));
+}
+
+#[test]
+fn failures_do_not_stop_processing() {
+    let dir = test_dir("report", "failures");
+    let missing = dir.join("missing.toml");
+    let invalid = dir.join("invalid.toml");
+    let formatted = dir.join("formatted.toml");
+    write(&invalid, "a = 1\nb = = 2\n");
+    write(&formatted, "a = 1\n");
+
+    let opt = Opt {
+        files: vec![missing, invalid, formatted],
+        check: true,
+        silent: true,
+        ..Default::default()
+    };
+
+    let report = toml_maid::run(opt, Config::default()).expect("to run without errors");
+
+    assert!(matches!(
+        report.files[0].outcome,
+        Outcome::Failed(Error::Io { .. })
+    ));
+    assert!(matches!(
+        report.files[1].outcome,
+        Outcome::Failed(Error::Parse { line: 2, .. })
+    ));
+    assert!(matches!(report.files[2].outcome, Outcome::Unchanged));
+    assert_eq!(report.exit_code(), 3);
+}
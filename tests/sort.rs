@@ -0,0 +1,38 @@
+use std::cmp::Ordering;
+
+use toml_edit::Value;
+use toml_maid::{compare_values, SortMode};
+
+fn compare(x: &str, y: &str) -> Ordering {
+    let x: Value = x.parse().expect("valid value");
+    let y: Value = y.parse().expect("valid value");
+    compare_values(&x, &y, SortMode::default(), None)
+}
+
+#[test]
+fn integers_and_floats_are_compared_exactly() {
+    // 2^53 and 2^53 + 1 are the same float.
+    assert_eq!(
+        compare("9007199254740992", "9007199254740992.0"),
+        Ordering::Equal
+    );
+    assert_eq!(
+        compare("9007199254740993", "9007199254740992.0"),
+        Ordering::Greater
+    );
+    assert_eq!(
+        compare("9007199254740992.0", "9007199254740993"),
+        Ordering::Less
+    );
+
+    assert_eq!(compare("1", "1.5"), Ordering::Less);
+    assert_eq!(compare("-1", "-1.5"), Ordering::Greater);
+    assert_eq!(compare("-0.0", "0"), Ordering::Equal);
+    assert_eq!(compare("9223372036854775807", "9.3e18"), Ordering::Less);
+    assert_eq!(
+        compare("-9223372036854775808", "-9.3e18"),
+        Ordering::Greater
+    );
+    assert_eq!(compare("1", "nan"), Ordering::Less);
+    assert_eq!(compare("inf", "1"), Ordering::Greater);
+}
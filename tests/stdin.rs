@@ -0,0 +1,58 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+fn run_stdin(args: &[&str], input: &str) -> (i32, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_toml-maid"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("to spawn toml-maid");
+
+    child
+        .stdin
+        .take()
+        .expect("stdin to be piped")
+        .write_all(input.as_bytes())
+        .expect("to write to stdin");
+
+    let output = child.wait_with_output().expect("to wait for toml-maid");
+    let stdout = String::from_utf8(output.stdout).expect("stdout to be utf8");
+
+    (output.status.code().expect("to have an exit code"), stdout)
+}
+
+#[test]
+fn formats_stdin_to_stdout() {
+    let (code, stdout) = run_stdin(&["-"], "b = 1\na = [ 'x','y' ]\n");
+
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "a = [ \"x\", \"y\" ]\nb = 1\n");
+}
+
+#[test]
+fn stdin_filepath_applies_excludes() {
+    let input = "b = 1\na = 2\n";
+    let (code, stdout) = run_stdin(
+        &[
+            "--stdin",
+            "--stdin-filepath",
+            "tests/output_consistency/foo.toml",
+        ],
+        input,
+    );
+
+    assert_eq!(code, 0);
+    assert_eq!(stdout, input, "excluded document should be left unchanged");
+}
+
+#[test]
+fn stdin_check_fails_without_output() {
+    let (code, stdout) = run_stdin(&["--stdin", "--check"], "b = 1\na = 2\n");
+
+    assert_eq!(code, 2);
+    assert!(stdout.is_empty());
+}